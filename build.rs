@@ -1,11 +1,20 @@
 use std::{fs, path::Path, env};
 use rand::random;
 
+// seed used with the `fixed-seed` feature
+const FIXED_SEED: u64 = 0x7374_7270_6f6f_6c00;
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=STRPOOL_SEED");
     let out_dir = env::var("OUT_DIR").unwrap();
 
+    let seed = match env::var("STRPOOL_SEED") {
+        Ok(seed) => seed.parse().expect("STRPOOL_SEED must be a u64"),
+        Err(_) if env::var_os("CARGO_FEATURE_FIXED_SEED").is_some() => FIXED_SEED,
+        Err(_) => random(),
+    };
+
     let rnd_seed_dst = Path::new(&out_dir).join("seed.dat");
-    let rnd_seed: [u8; 8] = random();
-    fs::write(rnd_seed_dst, &rnd_seed).unwrap();
+    fs::write(rnd_seed_dst, u64::to_ne_bytes(seed)).unwrap();
 }
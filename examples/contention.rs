@@ -0,0 +1,58 @@
+//! Threads interning overlapping strings into one subpool
+//!
+//! Each thread interns a stream of tokens drawn from the same strings,
+//! half of them from a small hot set, either one at a time or in
+//! batches through a `Stage`. Run with
+//! `cargo run --release --example contention`.
+
+use std::{thread, time::Instant};
+use strpool::Pool;
+
+const STRINGS: usize = 2000;
+const HOT: usize = 64;
+const TOKENS: usize = 20000;
+const BATCH: usize = 256;
+
+// returns the time per intern, in nanoseconds
+fn run(threads: usize, strings: &[String], staged: bool) -> f64 {
+    let pool: Pool<1> = Pool::new();
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        for offset in 0..threads {
+            let pool = &pool;
+            scope.spawn(move || {
+                // threads start at different strings, so they add new ones concurrently
+                let tokens: Vec<&str> = (0..TOKENS).map(|i| match i % 2 {
+                    0 => &strings[i / 2 % HOT],
+                    _ => &strings[(i / 2 + offset * 61) % STRINGS],
+                }).map(String::as_str).collect();
+
+                let mut stage = pool.stage();
+                let handles: Vec<_> = match staged {
+                    true => tokens.chunks(BATCH).flat_map(|batch| stage.intern_batch(batch)).collect(),
+                    false => tokens.iter().map(|token| pool.intern(token)).collect(),
+                };
+
+                assert!(handles.iter().zip(&tokens).all(|(handle, token)| handle == token));
+            });
+        }
+    });
+
+    let elapsed = start.elapsed();
+    assert!(strings.iter().all(|string| pool.contains(string)));
+    elapsed.as_nanos() as f64 / (threads * TOKENS) as f64
+}
+
+fn main() {
+    let strings: Vec<_> = (0..STRINGS).map(|i| format!("string number {}", i)).collect();
+    let cores = thread::available_parallelism().map_or(1, usize::from);
+    println!("{} core(s) available", cores);
+    println!("{:>8} {:>12} {:>12}", "threads", "intern", "staged");
+
+    for threads in [1, 4, 16] {
+        let one_by_one = run(threads, &strings, false);
+        let staged = run(threads, &strings, true);
+        println!("{:>8} {:>9.1} ns {:>9.1} ns", threads, one_by_one, staged);
+    }
+}
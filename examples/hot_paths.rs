@@ -0,0 +1,77 @@
+//! Timings of `PoolStr` hot paths
+//!
+//! Run with `cargo run --release --example hot_paths`.
+
+use std::{hint::black_box, time::Instant, hash::{Hash, Hasher, DefaultHasher}};
+use strpool::{Pool, PoolStr};
+
+const ROUNDS: usize = 200;
+
+fn strings() -> Vec<String> {
+    (0..1000).map(|i| format!("some/path/to/file_{}.rs", i)).collect()
+}
+
+// runs `f` ROUNDS times, each run making `calls` calls
+fn time<R>(name: &str, calls: usize, mut f: impl FnMut() -> R) -> R {
+    let start = Instant::now();
+    let mut result = f();
+
+    for _ in 1..ROUNDS {
+        result = black_box(f());
+    }
+
+    let nanos = start.elapsed().as_nanos() as f64 / (ROUNDS * calls) as f64;
+    println!("{:<32} {:>8.2} ns", name, nanos);
+    result
+}
+
+// equal strings in distinct pools don't share a slot,
+// so every comparison goes through their bytes
+fn equality(a: &[PoolStr<4>], b: &[PoolStr<4>]) {
+    let pairs: Vec<_> = (0..a.len()).flat_map(|i| [(i, i), (i, (i + 1) % a.len())]).collect();
+
+    let by_deref = time("eq: deref", pairs.len(), || {
+        pairs.iter().filter(|(i, j)| *a[*i] == *b[*j]).count()
+    });
+
+    let by_bytes = time("eq: bytes_eq", pairs.len(), || {
+        pairs.iter().filter(|(i, j)| a[*i].bytes_eq(&b[*j])).count()
+    });
+
+    assert_eq!(by_deref, by_bytes);
+    assert_eq!(by_bytes, a.len());
+}
+
+// composite hashes of the handles, four at a time
+fn hashing(name: &str, handles: &[PoolStr<4>]) {
+    let by_bytes = time(&format!("hash {}: Hash", name), handles.len(), || {
+        handles.chunks(4).map(|chunk| {
+            let mut state = DefaultHasher::new();
+            chunk.iter().for_each(|pool_str| pool_str.hash(&mut state));
+            state.finish()
+        }).fold(0, u64::wrapping_add)
+    });
+
+    let by_stored = time(&format!("hash {}: hash_into", name), handles.len(), || {
+        handles.chunks(4).map(|chunk| {
+            let mut state = DefaultHasher::new();
+            chunk.iter().for_each(|pool_str| pool_str.hash_into(&mut state));
+            state.finish()
+        }).fold(0, u64::wrapping_add)
+    });
+
+    black_box((by_bytes, by_stored));
+}
+
+fn main() {
+    let strings = strings();
+    let (pool_a, pool_b): (Pool<4>, Pool<4>) = (Pool::new(), Pool::new());
+    let a: Vec<_> = strings.iter().map(|string| pool_a.intern(string)).collect();
+    let b: Vec<_> = strings.iter().map(|string| pool_b.intern(string)).collect();
+
+    equality(&a, &b);
+    hashing("small", &a);
+
+    let large: Vec<_> = strings.iter().map(|string| pool_a.intern(&string.repeat(16))).collect();
+    hashing("large", &large);
+}
@@ -0,0 +1,47 @@
+//! Threads interning a few strings at a time through a `PoolCell`
+//!
+//! Run with `cargo run --release --features std --example pool_cell`.
+
+use std::{thread, time::Instant};
+use strpool::{PoolCell, OncePoolCell};
+
+const ROUNDS: usize = 20000;
+const STRINGS: [&str; 8] = ["GET", "POST", "Host", "Accept", "Content-Type", "Content-Length", "Connection", "keep-alive"];
+
+// returns the time per intern, in nanoseconds
+fn run(threads: usize, intern_round: impl Fn() + Sync) -> f64 {
+    let start = Instant::now();
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| (0..ROUNDS).for_each(|_| intern_round()));
+        }
+    });
+
+    start.elapsed().as_nanos() as f64 / (threads * ROUNDS * STRINGS.len()) as f64
+}
+
+fn main() {
+    let cores = thread::available_parallelism().map_or(1, usize::from);
+    println!("{} core(s) available", cores);
+    println!("{:>8} {:>12} {:>12} {:>12} {:>12}", "threads", "pool()", "intern", "with_pool", "OncePoolCell");
+
+    for threads in [1, 4, 16] {
+        let cell: PoolCell<4> = PoolCell::new();
+        let once_cell: OncePoolCell<4> = OncePoolCell::new();
+
+        // a clone of the pool per string
+        let by_pool = run(threads, || STRINGS.iter().for_each(|string| _ = cell.pool().intern(string)));
+
+        // one lock per string, without clone
+        let by_intern = run(threads, || STRINGS.iter().for_each(|string| _ = cell.intern(string)));
+
+        // one lock per round
+        let by_with_pool = run(threads, || cell.with_pool(|pool| STRINGS.iter().for_each(|string| _ = pool.intern(string))));
+
+        // no lock
+        let by_once_cell = run(threads, || once_cell.with_pool(|pool| STRINGS.iter().for_each(|string| _ = pool.intern(string))));
+
+        println!("{:>8} {:>9.1} ns {:>9.1} ns {:>9.1} ns {:>9.1} ns", threads, by_pool, by_intern, by_with_pool, by_once_cell);
+    }
+}
@@ -0,0 +1,494 @@
+use super::{Pool, PoolInner, PoolStr, bytes_from_len_u8, large::large_string_layout};
+use alloc::{vec, vec::Vec, collections::BTreeMap};
+use core::{str::{from_utf8, Utf8Error}, sync::atomic::Ordering::SeqCst};
+
+#[cfg(feature = "std")]
+use std::{collections::HashMap, string::String, time::{Duration, Instant}};
+
+/// What a batch of interns added to a pool
+///
+/// See [`Pool::intern_probe`]. Allocations from
+/// other threads during the batch are included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeStats {
+    /// Number of strings which weren't in the pool
+    pub new: usize,
+    /// Number of strings which were already in the pool
+    pub reused: usize,
+    /// Change in [`Pool::memory_usage`]
+    pub bytes_added: usize,
+    /// Number of small-string pages allocated
+    pub pages_added: usize,
+    /// Number of large strings allocated
+    pub large_allocations_added: usize,
+    /// Time spent interning the batch
+    #[cfg(feature = "std")]
+    pub elapsed: Duration,
+}
+
+/// Kind of allocation recorded by [`Pool::intern_traced`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocTier {
+    /// A page of small strings
+    Page,
+    /// A large string
+    LargeString,
+}
+
+/// Allocation recorded by [`Pool::intern_traced`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllocEvent {
+    /// What was allocated
+    pub tier: AllocTier,
+    /// Size of the allocation, in bytes
+    pub size: usize,
+    /// Index of the subpool it belongs to
+    pub subpool: usize,
+}
+
+/// Strings interned by one thread, see [`Pool::stage`]
+///
+/// Each handle is counted by the pool, so interning normally updates
+/// a counter shared by all threads for every string, after searching
+/// the shared pages. A stage remembers the strings it interned, so
+/// repeated ones are found without reading the pool, and updates the
+/// counter once per batch. This reduces contention when threads
+/// intern overlapping strings.
+///
+/// Strings are remembered until the stage is dropped.
+pub struct Stage<'a, const P: usize, const S: usize> {
+    inner: &'a PoolInner<P, S>,
+    // keyed by the bytes stored in the pool
+    slots: BTreeMap<&'a [u8], &'a u8>,
+}
+
+impl<'a, const P: usize, const S: usize> Stage<'a, P, S> {
+    /// Interns a batch of strings, updating the reference count once
+    pub fn intern_batch(&mut self, strings: &[&str]) -> Vec<PoolStr<P, S>> {
+        let inner = self.inner;
+        let mut counted = 0;
+
+        let mut slot_of = |bytes: &[u8]| {
+            if let Some(len_u8_ref) = self.slots.get(bytes) {
+                return Some(*len_u8_ref);
+            }
+
+            let len_u8_ref = match inner.find_slot(bytes) {
+                Some(len_u8_ref) => len_u8_ref,
+                None => {
+                    let (len_u8_ref, _) = inner.interned(inner.get_or_intern_slot(bytes))?;
+                    // this one is already accounted for in ref_count
+                    counted += 1;
+                    len_u8_ref
+                },
+            };
+
+            self.slots.insert(bytes_from_len_u8::<P, S>(len_u8_ref), len_u8_ref);
+            Some(len_u8_ref)
+        };
+
+        let batch: Vec<_> = strings.iter().map(|string| slot_of(string.as_bytes())).collect();
+        let handles = batch.iter().filter(|slot| slot.is_some()).count();
+        inner.ref_count.fetch_add(handles - counted, SeqCst);
+
+        batch.into_iter().map(|slot| match slot {
+            Some(len_u8_ref) => PoolStr::new(len_u8_ref),
+            None => PoolStr::empty(),
+        }).collect()
+    }
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Creates a new [`PoolStr`], pushing its allocations to `trace`
+    ///
+    /// Pages allocated by other threads in the same subpool
+    /// during the call are included.
+    pub fn intern_traced(&self, string: &str, trace: &mut Vec<AllocEvent>) -> PoolStr<P, S> {
+        let inner = self.inner();
+        let bytes = string.as_bytes();
+
+        if bytes.is_empty() {
+            return PoolStr::empty();
+        }
+
+        let subpool = inner.index_for(bytes);
+        let pages_before = inner.subpool_page_count(subpool);
+        let (pool_str, new) = inner.interned(inner.get_or_intern(string));
+
+        if !inner.is_small(bytes) {
+            if new {
+                trace.push(AllocEvent {
+                    tier: AllocTier::LargeString,
+                    size: large_string_layout::<P, S>(bytes.len()).size(),
+                    subpool,
+                });
+            }
+
+            return pool_str;
+        }
+
+        for _ in pages_before..inner.subpool_page_count(subpool) {
+            trace.push(AllocEvent {
+                tier: AllocTier::Page,
+                size: S,
+                subpool,
+            });
+        }
+
+        pool_str
+    }
+
+    /// Checks which of `queries` are in this pool
+    ///
+    /// Unlike [`Pool::find`], this doesn't update the reference count.
+    /// Queries are grouped by subpool, so each subpool is searched for
+    /// all of its queries in a row.
+    pub fn contains_all(&self, queries: &[&str]) -> Vec<bool> {
+        let inner = self.inner();
+        let mut found = vec![true; queries.len()];
+
+        let mut batch: Vec<_> = queries.iter().enumerate().filter(|(_, query)| !query.is_empty()).map(|(i, query)| {
+            let hash = inner.hash(query.as_bytes());
+            (inner.index_for_hashed(query.as_bytes(), hash), hash, i)
+        }).collect();
+
+        batch.sort_unstable_by_key(|(subpool, _, _)| *subpool);
+
+        for (_, hash, i) in batch {
+            found[i] = inner.find_slot_hashed(queries[i].as_bytes(), hash).is_some();
+        }
+
+        found
+    }
+
+    /// Creates a [`Stage`], to intern batches of strings from one thread
+    pub fn stage(&self) -> Stage<'_, P, S> {
+        Stage {
+            inner: self.inner(),
+            slots: BTreeMap::new(),
+        }
+    }
+
+    /// Interns a batch of strings, updating the reference count once
+    ///
+    /// See [`Stage::intern_batch`].
+    pub fn intern_batch(&self, strings: &[&str]) -> Vec<PoolStr<P, S>> {
+        self.stage().intern_batch(strings)
+    }
+
+    /// Validates and interns each record
+    ///
+    /// Invalid records don't prevent the others from being interned.
+    pub fn intern_records(&self, records: &[&[u8]]) -> Vec<Result<PoolStr<P, S>, Utf8Error>> {
+        records.iter().map(|record| Ok(self.intern(from_utf8(record)?))).collect()
+    }
+
+    /// Interns a batch of strings and reports what was added to the pool
+    pub fn intern_probe(&self, strings: &[&str]) -> ProbeStats {
+        let inner = self.inner();
+        let bytes_before = self.memory_usage();
+        let pages_before = inner.page_count();
+        let large_before = inner.large_string_count();
+        let (mut new, mut reused) = (0, 0);
+
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+
+        for string in strings {
+            match inner.interned(inner.get_or_intern(string)).1 {
+                true => new += 1,
+                false => reused += 1,
+            }
+        }
+
+        ProbeStats {
+            new,
+            reused,
+            bytes_added: self.memory_usage() - bytes_before,
+            pages_added: inner.page_count() - pages_before,
+            large_allocations_added: inner.large_string_count() - large_before,
+            #[cfg(feature = "std")]
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// Interns object keys, returning them in sorted order
+    ///
+    /// The order only depends on the keys, which helps
+    /// building canonical representations of objects.
+    pub fn intern_object_keys(&self, keys: &[&str]) -> Vec<PoolStr<P, S>> {
+        let mut interned: Vec<_> = keys.iter().map(|key| self.intern(key)).collect();
+        interned.sort_unstable();
+        interned
+    }
+
+    /// Interns a column of values, run-length encoded
+    ///
+    /// Each run of identical consecutive values is interned once and
+    /// returned with its length. Runs longer than `u32::MAX` are split.
+    pub fn intern_rle(&self, sorted: &[&str]) -> Vec<(PoolStr<P, S>, u32)> {
+        let mut runs: Vec<(PoolStr<P, S>, u32)> = Vec::new();
+
+        for string in sorted {
+            match runs.last_mut() {
+                Some((pool_str, count)) if &**pool_str == *string && *count < u32::MAX => *count += 1,
+                _ => runs.push((self.intern(string), 1)),
+            }
+        }
+
+        runs
+    }
+
+    /// Dictionary-encodes a column of values
+    ///
+    /// This returns the sorted, unique values of `column` and, for
+    /// each row, the index of its value in that dictionary.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `u32::MAX` unique values.
+    pub fn dictionary_encode(&self, column: &[&str]) -> (Vec<PoolStr<P, S>>, Vec<u32>) {
+        let mut codes: BTreeMap<&str, u32> = column.iter().map(|value| (*value, 0)).collect();
+        let mut dictionary = Vec::with_capacity(codes.len());
+
+        for (value, code) in codes.iter_mut() {
+            *code = u32::try_from(dictionary.len()).expect("dictionary_encode: too many values");
+            dictionary.push(self.intern(value));
+        }
+
+        let rows = column.iter().map(|value| codes[value]).collect();
+        (dictionary, rows)
+    }
+
+    /// Interns the endpoints of graph edges
+    ///
+    /// Each vertex is interned once. The unique vertices are
+    /// returned as well, in order of first appearance.
+    pub fn intern_edges(&self, edges: &[(&str, &str)]) -> (Vec<(PoolStr<P, S>, PoolStr<P, S>)>, Vec<PoolStr<P, S>>) {
+        let mut vertices = Vec::new();
+        let mut seen = BTreeMap::new();
+
+        let mut intern = |vertex| seen.entry(vertex).or_insert_with(|| {
+            let pool_str = self.intern(vertex);
+            vertices.push(pool_str.clone());
+            pool_str
+        }).clone();
+
+        let edges = edges.iter().map(|(src, dst)| (intern(*src), intern(*dst))).collect();
+        (edges, vertices)
+    }
+
+    /// Builds a map keyed by [`PoolStr`] from a `String`-keyed map
+    ///
+    /// The new map can still be queried with `&str` keys.
+    #[cfg(feature = "std")]
+    pub fn intern_map_keys<'a, V>(&self, map: &'a HashMap<String, V>) -> HashMap<PoolStr<P, S>, &'a V> {
+        map.iter().map(|(key, value)| (self.intern(key), value)).collect()
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn intern_map_keys() {
+    let mut map = HashMap::new();
+    map.insert(String::from("one"), 1);
+    map.insert(String::from("two"), 2);
+
+    let pool: Pool<1> = Pool::new();
+    let interned = pool.intern_map_keys(&map);
+
+    assert_eq!(interned.len(), 2);
+    assert_eq!(interned.get("one"), Some(&&1));
+    assert_eq!(interned.get("two"), Some(&&2));
+    assert_eq!(interned.get("three"), None);
+}
+
+#[test]
+fn intern_object_keys() {
+    let pool: Pool<4> = Pool::new();
+    let a = pool.intern_object_keys(&["name", "id", "email"]);
+    let b = pool.intern_object_keys(&["email", "name", "id"]);
+
+    assert_eq!(a, ["email", "id", "name"]);
+    assert_eq!(a, b);
+}
+
+#[test]
+fn intern_probe() {
+    use super::LARGE_STRING;
+
+    let pool: Pool<1> = Pool::new();
+    pool.intern("gjnberguieriu");
+
+    let stats = pool.intern_probe(&["gjnberguieriu", "ryjtyjty", LARGE_STRING, "ryjtyjty", LARGE_STRING, ""]);
+    assert_eq!((stats.new, stats.reused), (2, 4));
+    assert_eq!((stats.pages_added, stats.large_allocations_added), (0, 1));
+    assert!(stats.bytes_added > LARGE_STRING.len());
+
+    let stats = pool.intern_probe(&["gjnberguieriu", "ryjtyjty"]);
+    assert_eq!((stats.new, stats.reused, stats.bytes_added), (0, 2, 0));
+}
+
+#[test]
+fn intern_records() {
+    let pool: Pool<4> = Pool::new();
+    let records: [&[u8]; 4] = [b"valid", b"\xff\xfe", "caf\u{e9}".as_bytes(), b"caf\xe9"];
+    let results = pool.intern_records(&records);
+
+    assert_eq!(results[0].as_ref().unwrap(), "valid");
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap(), "café");
+    assert_eq!(results[3].as_ref().unwrap_err().valid_up_to(), 3);
+    assert_eq!(pool.len(), 2);
+}
+
+#[test]
+fn contains_all() {
+    use super::LARGE_STRING;
+    use core::sync::atomic::Ordering::Relaxed;
+
+    let pool: Pool<4> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.intern(LARGE_STRING);
+
+    let queries = ["gjnberguieriu", "yikes", "", LARGE_STRING, &LARGE_STRING[1..]];
+    assert_eq!(pool.contains_all(&queries), [true, false, true, true, false]);
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1);
+
+    // results keep the order of the queries, across subpools
+    let strings: Vec<_> = (0..64).map(|i| alloc::format!("string number {}", i)).collect();
+    strings.iter().step_by(2).for_each(|string| _ = pool.intern(string));
+    let queries: Vec<&str> = strings.iter().map(|string| &**string).collect();
+    let found = pool.contains_all(&queries);
+    assert!(found.iter().enumerate().all(|(i, found)| *found == (i % 2 == 0)));
+}
+
+#[test]
+fn intern_batch() {
+    use super::LARGE_STRING;
+    use core::sync::atomic::Ordering::Relaxed;
+
+    let pool: Pool<4> = Pool::new();
+    let yikes = pool.intern("yikes");
+
+    let batch = pool.intern_batch(&["a", "yikes", "a", "", LARGE_STRING, "b", LARGE_STRING]);
+    assert_eq!(batch, ["a", "yikes", "a", "", LARGE_STRING, "b", LARGE_STRING]);
+    assert_eq!(batch[1], yikes);
+    assert_eq!(batch[0].raw_slot().unwrap().as_ptr(), batch[2].raw_slot().unwrap().as_ptr());
+    assert_eq!(pool.len(), 4);
+
+    // one count for the pool, one per non-empty handle
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 8);
+    drop((batch, yikes));
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1);
+
+    // a stage finds the strings it interned without reading the pool
+    let mut stage = pool.stage();
+    let first = stage.intern_batch(&["c", LARGE_STRING]);
+    assert_eq!(stage.slots.len(), 2);
+    let second = stage.intern_batch(&["c", "d", LARGE_STRING]);
+    assert_eq!(second, ["c", "d", LARGE_STRING]);
+    assert_eq!(first[0], second[0]);
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 6);
+    drop((stage, first, second));
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1);
+}
+
+#[test]
+fn concurrent_stages() {
+    use alloc::format;
+    use core::sync::atomic::Ordering::Relaxed;
+
+    let pool: Pool<1> = Pool::new();
+    let strings: Vec<_> = (0..500).map(|i| format!("string number {}", i)).collect();
+
+    std::thread::scope(|scope| {
+        for offset in 0..8 {
+            let (pool, strings) = (&pool, &strings);
+            scope.spawn(move || {
+                let mut stage = pool.stage();
+                let tokens: Vec<&str> = (0..2000).map(|i| &*strings[(i + offset * 61) % strings.len()]).collect();
+
+                for batch in tokens.chunks(64) {
+                    assert_eq!(stage.intern_batch(batch), batch);
+                }
+            });
+        }
+    });
+
+    assert!(strings.iter().all(|string| pool.contains(string)));
+    assert_eq!(pool.len() - pool.audit_duplicates(), strings.len());
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1);
+}
+
+#[test]
+fn intern_rle() {
+    let pool: Pool<4> = Pool::new();
+
+    let column = ["a", "a", "a", "b", "c", "c", "", ""];
+    let runs = pool.intern_rle(&column);
+    let lengths: Vec<_> = runs.iter().map(|(pool_str, count)| (&**pool_str, *count)).collect();
+    assert_eq!(lengths, [("a", 3), ("b", 1), ("c", 2), ("", 2)]);
+    assert_eq!(runs[2].0, pool.find("c").unwrap());
+    assert_eq!(pool.len(), 3);
+
+    let single = pool.intern_rle(&["yikes"; 1000]);
+    assert_eq!(single.len(), 1);
+    assert_eq!((&*single[0].0, single[0].1), ("yikes", 1000));
+
+    assert!(pool.intern_rle(&[]).is_empty());
+}
+
+#[test]
+fn intern_edges() {
+    let pool: Pool<4> = Pool::new();
+    let (edges, vertices) = pool.intern_edges(&[("a", "b"), ("b", "c"), ("a", "c"), ("c", "c")]);
+
+    assert_eq!(vertices, ["a", "b", "c"]);
+    assert_eq!(edges.len(), 4);
+    assert_eq!((&*edges[1].0, &*edges[1].1), ("b", "c"));
+    assert_eq!(edges[0].0.raw_slot().unwrap().as_ptr(), edges[2].0.raw_slot().unwrap().as_ptr());
+    assert_eq!(edges[3].0, edges[3].1);
+    assert_eq!(pool.len(), 3);
+}
+
+#[test]
+fn dictionary_encode() {
+    let pool: Pool<4> = Pool::new();
+    let column = ["fr", "de", "fr", "", "us", "de", "fr"];
+    let (dictionary, codes) = pool.dictionary_encode(&column);
+
+    assert_eq!(dictionary, ["", "de", "fr", "us"]);
+    assert_eq!(codes, [2, 1, 2, 0, 3, 1, 2]);
+    assert!(codes.iter().map(|code| &*dictionary[*code as usize]).eq(column));
+    assert_eq!(pool.len(), 3);
+}
+
+#[test]
+fn intern_traced() {
+    use alloc::string::String;
+
+    let pool: Pool<1, 256> = Pool::new();
+    let mut trace = Vec::new();
+    let half_page = |c| String::from(c).repeat(119);
+
+    // two slots of 120 bytes fit in a page
+    pool.intern_traced(&half_page('a'), &mut trace);
+    pool.intern_traced(&half_page('b'), &mut trace);
+    pool.intern_traced(&half_page('c'), &mut trace);
+    pool.intern_traced(&half_page('a'), &mut trace);
+
+    let large_string = half_page('d') + &half_page('d');
+    pool.intern_traced(&large_string, &mut trace);
+    pool.intern_traced(&large_string, &mut trace);
+    pool.intern_traced("", &mut trace);
+
+    let page = AllocEvent { tier: AllocTier::Page, size: 256, subpool: 0 };
+    let large = AllocEvent {
+        tier: AllocTier::LargeString,
+        size: large_string_layout::<1, 256>(238).size(),
+        subpool: 0,
+    };
+
+    assert_eq!(trace, [page.clone(), page, large]);
+}
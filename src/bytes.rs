@@ -0,0 +1,224 @@
+use super::{Pool, PoolStr, pool_ptr_of, release, bytes_from_len_u8};
+use core::{ops::Deref, borrow::Borrow, hash::{Hash, Hasher}, cmp::Ordering};
+use core::{ptr::null, str::{from_utf8, Utf8Error}, mem::forget};
+
+/// `&[u8]` equivalent
+///
+/// Byte strings share the storage of [`PoolStr`]s,
+/// so equal contents are only stored once.
+pub struct PoolBytes<const P: usize, const S: usize = 1024> {
+    len_ptr: *const u8,
+    _phantom: [(); P],
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Locates an existing [`PoolBytes`]
+    ///
+    /// See [`Pool::find`].
+    pub fn find_bytes(&self, bytes: &[u8]) -> Option<PoolBytes<P, S>> {
+        if bytes.is_empty() {
+            return Some(PoolBytes::empty());
+        }
+
+        let inner = self.inner();
+        let len_u8_ref = inner.find_slot(bytes)?;
+        inner.inc_ref_count();
+        Some(PoolBytes::new(len_u8_ref))
+    }
+
+    /// Creates a new [`PoolBytes`]
+    ///
+    /// Unlike [`Pool::intern`], `bytes` don't have to be valid UTF-8.
+    pub fn intern_bytes(&self, bytes: &[u8]) -> PoolBytes<P, S> {
+        let inner = self.inner();
+        match inner.interned(inner.get_or_intern_slot(bytes)) {
+            Some((len_u8_ref, _)) => PoolBytes::new(len_u8_ref),
+            None => PoolBytes::empty(),
+        }
+    }
+}
+
+impl<const P: usize, const S: usize> PoolBytes<P, S> {
+    fn new(len: &u8) -> Self {
+        Self {
+            len_ptr: len as *const u8,
+            _phantom: [(); P],
+        }
+    }
+
+    /// The empty byte string
+    pub fn empty() -> Self {
+        Self {
+            len_ptr: null(),
+            _phantom: [(); P],
+        }
+    }
+
+    /// Returns these bytes as a [`PoolStr`] if they are valid UTF-8
+    pub fn to_pool_str(&self) -> Result<PoolStr<P, S>, Utf8Error> {
+        from_utf8(self)?;
+
+        let pool_str = match unsafe { self.len_ptr.as_ref() } {
+            Some(len_u8_ref) => PoolStr::new(len_u8_ref),
+            None => PoolStr::empty(),
+        };
+
+        // the reference of the clone now belongs to pool_str
+        forget(self.clone());
+        Ok(pool_str)
+    }
+}
+
+impl<const P: usize, const S: usize> From<PoolStr<P, S>> for PoolBytes<P, S> {
+    fn from(pool_str: PoolStr<P, S>) -> Self {
+        let len_ptr = pool_str.len_ptr;
+        // its reference now belongs to the PoolBytes
+        forget(pool_str);
+
+        Self {
+            len_ptr,
+            _phantom: [(); P],
+        }
+    }
+}
+
+impl<const P: usize, const S: usize> Deref for PoolBytes<P, S> {
+    type Target = [u8];
+    fn deref(&self) -> &Self::Target {
+        match unsafe { self.len_ptr.as_ref() } {
+            Some(len_u8_ptr) => bytes_from_len_u8::<P, S>(len_u8_ptr),
+            None => &[],
+        }
+    }
+}
+
+impl<const P: usize, const S: usize> Drop for PoolBytes<P, S> {
+    fn drop(&mut self) {
+        release::<P, S>(self.len_ptr);
+    }
+}
+
+impl<const P: usize, const S: usize> Clone for PoolBytes<P, S> {
+    fn clone(&self) -> Self {
+        if let Some(pool_ptr) = pool_ptr_of::<P, S>(self.len_ptr) {
+            unsafe { pool_ptr.as_ref() }.unwrap().inc_ref_count();
+        }
+
+        Self {
+            len_ptr: self.len_ptr,
+            _phantom: [(); P],
+        }
+    }
+}
+
+// Safe because of proper atomic operations
+unsafe impl<const P: usize, const S: usize> Send for PoolBytes<P, S> {}
+unsafe impl<const P: usize, const S: usize> Sync for PoolBytes<P, S> {}
+
+impl<const P: usize, const S: usize> core::fmt::Debug for PoolBytes<P, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.deref().fmt(f)
+    }
+}
+
+impl<const P: usize, const S: usize> PartialEq for PoolBytes<P, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len_ptr == other.len_ptr || self.deref() == other.deref()
+    }
+}
+
+impl<const P: usize, const S: usize> Eq for PoolBytes<P, S> {}
+
+impl<const P: usize, const S: usize> PartialEq<[u8]> for PoolBytes<P, S> {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.deref() == other
+    }
+}
+
+impl<const P: usize, const S: usize> PartialEq<&[u8]> for PoolBytes<P, S> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.deref() == *other
+    }
+}
+
+impl<const P: usize, const S: usize> PartialOrd for PoolBytes<P, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const P: usize, const S: usize> Ord for PoolBytes<P, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deref().cmp(other.deref())
+    }
+}
+
+impl<const P: usize, const S: usize> Hash for PoolBytes<P, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.deref().hash(state);
+    }
+}
+
+impl<const P: usize, const S: usize> Borrow<[u8]> for PoolBytes<P, S> {
+    fn borrow(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const P: usize, const S: usize> AsRef<[u8]> for PoolBytes<P, S> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const P: usize, const S: usize> Default for PoolBytes<P, S> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+#[test]
+fn intern_bytes() {
+    let large_bytes = [0xff; 200];
+
+    let pool: Pool<4> = Pool::new();
+    let tag = pool.intern_bytes(b"\x08\x96\x01");
+    let large = pool.intern_bytes(&large_bytes);
+
+    assert_eq!(tag, &b"\x08\x96\x01"[..]);
+    assert_eq!(large, &large_bytes[..]);
+    assert_eq!(pool.find_bytes(b"\x08\x96\x01"), Some(tag.clone()));
+    assert_eq!(pool.find_bytes(&large_bytes), Some(large));
+    assert_eq!(pool.find_bytes(b"\xfe"), None);
+    assert!(tag.to_pool_str().is_err());
+    assert_eq!(pool.len(), 2);
+
+    // strings are shared
+    let text = pool.intern_bytes(b"text");
+    assert_eq!(pool.find("text"), Some(text.to_pool_str().unwrap()));
+    assert_eq!(PoolBytes::from(pool.intern("text")), text);
+    assert_eq!(pool.len(), 3);
+
+    assert_eq!(pool.intern_bytes(b""), PoolBytes::empty());
+    assert_eq!(&*PoolBytes::<4>::empty().to_pool_str().unwrap(), "");
+}
+
+#[test]
+fn non_utf8_entries() {
+    use alloc::{format, vec::Vec};
+
+    let pool: Pool<4> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.intern_bytes(b"\xff\xfe");
+    pool.intern_bytes(&[0xff; 200]);
+
+    // only strings are visited
+    assert_eq!(pool.iter().collect::<Vec<_>>(), ["gjnberguieriu"]);
+    assert_eq!(pool.iter_pool_str().count(), 1);
+    assert_eq!(pool.audit_duplicates(), 0);
+    assert!(format!("{:?}", pool).contains("[255, 254]"));
+
+    let copy = pool.deep_clone();
+    assert_eq!(copy.len(), 3);
+    assert!(copy.find_bytes(b"\xff\xfe").is_some());
+}
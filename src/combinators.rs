@@ -0,0 +1,178 @@
+use super::PoolStr;
+use alloc::{string::String, vec::Vec};
+
+impl<const P: usize, const S: usize> PoolStr<P, S> {
+    /// Interns this string repeated `n` times, in the same pool
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the result would overflow.
+    pub fn repeat_interned(&self, n: usize) -> PoolStr<P, S> {
+        let len = self.len().checked_mul(n).expect("repeat_interned: length overflow");
+
+        match self.pool_inner() {
+            Some(pool) if len != 0 => pool.intern(&self.repeat(n)),
+            _ => PoolStr::empty(),
+        }
+    }
+
+    /// Interns this string followed by `suffix`, in the same pool
+    ///
+    /// The empty string belongs to no pool, so `None` is returned if
+    /// this is the empty string and `suffix` isn't; intern `suffix`
+    /// in a pool of your choice in that case.
+    pub fn concat_str(&self, suffix: &str) -> Option<PoolStr<P, S>> {
+        if suffix.is_empty() {
+            return Some(self.clone());
+        }
+
+        let pool = self.pool_inner()?;
+
+        let mut string = String::with_capacity(self.len() + suffix.len());
+        string.push_str(self);
+        string.push_str(suffix);
+
+        Some(pool.intern(&string))
+    }
+
+    /// Interns both sides of the first `sep` in this string, in the same pool
+    pub fn split_once_interned(&self, sep: char) -> Option<(PoolStr<P, S>, PoolStr<P, S>)> {
+        let (before, after) = self.split_once(sep)?;
+        // a non-empty string has a pool
+        let pool = self.pool_inner()?;
+        Some((pool.intern(before), pool.intern(after)))
+    }
+
+    /// Lazily interns the fields of this string, split on `sep`
+    ///
+    /// Fields are interned in the same pool as they are yielded.
+    pub fn fields(&self, sep: char) -> impl Iterator<Item = PoolStr<P, S>> + '_ {
+        let pool = self.pool_inner();
+
+        self.split(sep).map(move |field| match pool {
+            Some(pool) => pool.intern(field),
+            None => PoolStr::empty(),
+        })
+    }
+
+    /// Interns the lines of this string, in the same pool
+    ///
+    /// Lines are split like [`str::lines`]: on `\n` or `\r\n`,
+    /// and a trailing line ending doesn't yield an empty line.
+    pub fn lines_interned(&self) -> Vec<PoolStr<P, S>> {
+        match self.pool_inner() {
+            Some(pool) => self.lines().map(|line| pool.intern(line)).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[test]
+fn repeat_interned() {
+    use super::Pool;
+
+    let pool: Pool<4> = Pool::new();
+    let dash = pool.intern("-");
+
+    assert_eq!(&*dash.repeat_interned(3), "---");
+    assert_eq!(pool.find("---"), Some(dash.repeat_interned(3)));
+    assert_eq!(&*dash.repeat_interned(200), &*"-".repeat(200));
+    assert_eq!(pool.find(&"-".repeat(200)), Some(dash.repeat_interned(200)));
+
+    assert_eq!(&*dash.repeat_interned(0), "");
+    assert_eq!(&*PoolStr::<4>::empty().repeat_interned(5), "");
+}
+
+#[test]
+#[should_panic]
+fn repeat_interned_overflow() {
+    use super::Pool;
+
+    let pool: Pool<4> = Pool::new();
+    pool.intern("--").repeat_interned(usize::MAX);
+}
+
+#[test]
+fn concat_str() {
+    use super::{Pool, SMALL_MAX};
+
+    let pool: Pool<4> = Pool::new();
+    let name = pool.intern("main");
+
+    assert_eq!(name.concat_str(".rs").as_deref(), Some("main.rs"));
+    assert_eq!(pool.find("main.rs"), name.concat_str(".rs"));
+    assert_eq!(name.concat_str(""), Some(name));
+
+    // the empty string has no pool to intern into
+    assert_eq!(PoolStr::<4>::empty().concat_str(""), Some(PoolStr::empty()));
+    assert_eq!(PoolStr::<4>::empty().concat_str(".rs"), None);
+    assert_eq!(pool.find(".rs"), None);
+
+    // crossing the small/large boundary
+    let almost = pool.intern(&"-".repeat(SMALL_MAX - 1));
+    assert!(almost.concat_str("-").unwrap().raw_slot().is_some());
+    assert!(almost.concat_str("--").unwrap().raw_slot().is_none());
+    assert_eq!(pool.find(&"-".repeat(SMALL_MAX + 1)), almost.concat_str("--"));
+}
+
+#[test]
+fn fields() {
+    use super::Pool;
+    use alloc::vec::Vec;
+
+    let pool: Pool<4> = Pool::new();
+
+    for string in ["a,b,c", "a,,c,", ",", "no_sep"] {
+        let line = pool.intern(string);
+        let fields: Vec<_> = line.fields(',').collect();
+        assert!(fields.iter().map(|f| &**f).eq(string.split(',')));
+    }
+
+    let empty: Vec<_> = PoolStr::<4>::empty().fields(',').collect();
+    assert_eq!(empty, [""]);
+
+    // only visited fields are interned
+    let line = pool.intern("one;two;three");
+    let first: Vec<_> = line.fields(';').take(2).collect();
+    assert_eq!(first, ["one", "two"]);
+    assert_eq!(pool.find("three"), None);
+}
+
+#[test]
+fn split_once_interned() {
+    use super::Pool;
+
+    let pool: Pool<4> = Pool::new();
+
+    let (key, value) = pool.intern("key=value=1").split_once_interned('=').unwrap();
+    assert_eq!((&*key, &*value), ("key", "value=1"));
+    assert_eq!(pool.find("key"), Some(key));
+
+    let (key, value) = pool.intern("=value").split_once_interned('=').unwrap();
+    assert_eq!((&*key, &*value), ("", "value"));
+
+    let (key, value) = pool.intern("key=").split_once_interned('=').unwrap();
+    assert_eq!((&*key, &*value), ("key", ""));
+
+    assert_eq!(pool.intern("no_sep").split_once_interned('='), None);
+    assert_eq!(PoolStr::<4>::empty().split_once_interned('='), None);
+}
+
+#[test]
+fn lines_interned() {
+    use super::Pool;
+
+    let pool: Pool<4> = Pool::new();
+
+    for text in ["one\ntwo\r\n\nthree", "trailing\n", "\n\n", "single"] {
+        let lines = pool.intern(text).lines_interned();
+        assert!(lines.iter().map(|line| &**line).eq(text.lines()));
+
+        for line in text.lines() {
+            assert_eq!(pool.find(line).as_deref(), Some(line));
+        }
+    }
+
+    assert_eq!(pool.find("two\r"), None);
+    assert!(PoolStr::<4>::empty().lines_interned().is_empty());
+}
@@ -0,0 +1,383 @@
+use super::{Pool, PoolHasher, hash::CityHash};
+use alloc::alloc::{GlobalAlloc, Layout};
+
+/// Settings for a new [`Pool`]
+///
+/// ```rust
+/// # use strpool::{Pool, PoolConfig};
+/// let pool: Pool<4> = PoolConfig::new().seed(42).build();
+/// ```
+#[derive(Clone)]
+pub struct PoolConfig {
+    pub(crate) seed: Option<u64>,
+    pub(crate) small_max: usize,
+    pub(crate) shard: Option<fn(&str) -> usize>,
+    pub(crate) max_unique: usize,
+    pub(crate) allocator: &'static (dyn GlobalAlloc + Sync),
+    pub(crate) hasher: &'static dyn PoolHasher,
+    pub(crate) fallible: bool,
+}
+
+// forwards to the global allocator
+struct DefaultAlloc;
+
+unsafe impl GlobalAlloc for DefaultAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        alloc::alloc::alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        alloc::alloc::dealloc(ptr, layout)
+    }
+}
+
+/// Maximum length of small strings
+///
+/// Strings up to this length are stored in shared pages,
+/// longer strings get their own allocation.
+pub const SMALL_MAX: usize = 126;
+
+impl PoolConfig {
+    /// Default settings
+    pub fn new() -> Self {
+        Self {
+            seed: None,
+            small_max: SMALL_MAX,
+            shard: None,
+            max_unique: usize::MAX,
+            allocator: &DefaultAlloc,
+            hasher: &CityHash,
+            fallible: false,
+        }
+    }
+
+    /// Sets the seed used to hash strings
+    ///
+    /// By default, a seed is chosen randomly when this crate is built.
+    /// A fixed seed makes the placement of strings deterministic.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the maximum length of small strings
+    ///
+    /// This can only be lowered from the default, [`SMALL_MAX`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `small_max` is greater than [`SMALL_MAX`].
+    pub fn small_max(mut self, small_max: usize) -> Self {
+        assert!(small_max <= SMALL_MAX, "small_max cannot exceed {}", SMALL_MAX);
+        self.small_max = small_max;
+        self
+    }
+
+    /// Sets the function choosing the subpool of each string
+    ///
+    /// By default, strings are placed according to their hash.
+    /// With this, the subpool is `shard(string) % P` instead,
+    /// which can be used to keep related strings together. Byte
+    /// strings which aren't valid UTF-8 are placed by their hash.
+    pub fn shard_by(mut self, shard: fn(&str) -> usize) -> Self {
+        self.shard = Some(shard);
+        self
+    }
+
+    /// Sets the maximum number of unique strings
+    ///
+    /// Once the pool holds `max_unique` strings, [`Pool::intern_with_limit`]
+    /// refuses new strings with [`InternError::Limit`]. By default, there
+    /// is no limit.
+    ///
+    /// [`InternError::Limit`]: crate::InternError::Limit
+    pub fn max_unique(mut self, max_unique: usize) -> Self {
+        self.max_unique = max_unique;
+        self
+    }
+
+    /// Sets the hash function placing strings in subpools
+    ///
+    /// By default, CityHash is used. Together with a fixed [`seed`](Self::seed),
+    /// this can make placement identical across builds and processes.
+    pub fn hasher(mut self, hasher: &'static dyn PoolHasher) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Sets the allocator of pages and large strings
+    ///
+    /// By default, they come from the global allocator. Everything
+    /// is freed through the same allocator when the pool is dropped.
+    pub fn allocator(mut self, allocator: &'static (dyn GlobalAlloc + Sync)) -> Self {
+        self.allocator = allocator;
+        self
+    }
+
+    /// Makes allocation failures panic with an [`InternError`] payload
+    ///
+    /// Methods which can't return an error, like [`Pool::intern`],
+    /// panic when memory can't be allocated. By default, the payload
+    /// is a message. With this, it is [`InternError::Alloc`], so it can
+    /// be told apart after [`catch_unwind`](std::panic::catch_unwind).
+    ///
+    /// [`InternError`]: crate::InternError
+    /// [`InternError::Alloc`]: crate::InternError::Alloc
+    #[cfg(feature = "std")]
+    pub fn fallible(mut self, fallible: bool) -> Self {
+        self.fallible = fallible;
+        self
+    }
+
+    /// Creates a new pool with these settings
+    pub fn build<const P: usize, const S: usize>(self) -> Pool<P, S> {
+        Pool::with_config(self)
+    }
+}
+
+impl core::fmt::Debug for PoolConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PoolConfig")
+            .field("seed", &self.seed)
+            .field("small_max", &self.small_max)
+            .field("shard", &self.shard)
+            .field("max_unique", &self.max_unique)
+            .field("fallible", &self.fallible)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn pool_config() {
+    use cityhasher::hash_with_seed;
+    use super::PoolInner;
+
+    let strings = ["gjnberguieriu", "krjgegyhergyeurgyeyrg", "ryjtyjty", "yikes"];
+
+    for seed in [0, 42] {
+        let pool: Pool<16> = PoolConfig::new().seed(seed).build();
+
+        for string in strings {
+            pool.intern(string);
+            let pool_index = PoolInner::<16, 1024>::index_from_hash(hash_with_seed(string, seed));
+            let mut subpool = pool.inner().small_slots(pool_index);
+            assert!(subpool.any(|len_u8_ref| super::string_from_len_u8::<16, 1024>(len_u8_ref) == string));
+        }
+    }
+
+    let default: Pool<16> = PoolConfig::new().build();
+    default.intern("yikes");
+    assert_eq!(&*default.find("yikes").unwrap(), "yikes");
+}
+
+#[test]
+fn small_max() {
+    let string_10 = "0123456789";
+    let string_20 = "01234567890123456789";
+
+    for small_max in [SMALL_MAX, 15, 0] {
+        let pool: Pool<4> = PoolConfig::new().small_max(small_max).build();
+
+        for string in [string_10, string_20] {
+            let pool_str = pool.intern(string);
+            assert_eq!(pool_str.raw_slot().is_some(), string.len() <= small_max);
+            assert_eq!(pool.find(string), Some(pool_str));
+        }
+
+        assert_eq!(pool.len(), 2);
+    }
+}
+
+#[test]
+fn shard_by() {
+    use super::LARGE_STRING;
+
+    let large_string = &*alloc::format!("1:{}", &LARGE_STRING[1..]);
+
+    // keys are prefixed with a tenant id
+    fn tenant(string: &str) -> usize {
+        string[..1].parse().unwrap()
+    }
+
+    let pool: Pool<4> = PoolConfig::new().shard_by(tenant).build();
+
+    for string in ["0:yikes", "1:yikes", "2:ryjtyjty", "6:gjnberguieriu", large_string] {
+        let pool_index = tenant(string) % 4;
+        let pool_str = pool.intern(string);
+
+        let (mut small, mut large) = (pool.inner().small_slots(pool_index), pool.inner().large_slots(pool_index));
+        let slot = |len_u8_ref: &u8| core::ptr::eq(len_u8_ref, pool_str.len_ptr);
+        assert!(small.any(slot) || large.any(slot));
+        assert_eq!(pool.find(string), Some(pool_str));
+    }
+
+    assert_eq!(pool.len(), 5);
+    assert_eq!(pool.find("3:yikes"), None);
+}
+
+#[test]
+fn max_unique() {
+    use super::{InternError, LARGE_STRING, test_alloc};
+
+    let large_string = &*alloc::format!("{}000", LARGE_STRING);
+
+    let pool: Pool<4> = PoolConfig::new().max_unique(3).build();
+    let a = pool.intern_with_limit("a").unwrap();
+    pool.intern_with_limit(large_string).unwrap();
+    pool.intern_with_limit("b").unwrap();
+
+    // existing strings can still be interned
+    assert_eq!(pool.intern_with_limit("a"), Ok(a));
+    assert_eq!(pool.intern_with_limit(large_string).as_deref(), Ok(large_string));
+    assert_eq!(pool.intern_with_limit("").as_deref(), Ok(""));
+
+    assert_eq!(pool.intern_with_limit("c"), Err(InternError::Limit));
+    assert_eq!(pool.intern_with_limit(&large_string[1..]), Err(InternError::Limit));
+    assert_eq!(pool.len(), 3);
+
+    // strings interned without a limit count too
+    let pool: Pool<4> = PoolConfig::new().max_unique(2).build();
+    pool.intern("a");
+    pool.intern_adjacent("b", "c");
+    assert_eq!(pool.intern_with_limit("d"), Err(InternError::Limit));
+    assert_eq!(pool.intern_with_limit("c").as_deref(), Ok("c"));
+
+    // a failed allocation releases its reservation
+    let pool: Pool<4> = PoolConfig::new().max_unique(1).build();
+    test_alloc::set_failing(true);
+    let failed = pool.intern_with_limit("a");
+    test_alloc::set_failing(false);
+    assert_eq!(failed, Err(InternError::Alloc));
+    assert_eq!(pool.intern_with_limit("b").as_deref(), Ok("b"));
+    assert_eq!(pool.intern_with_limit("a"), Err(InternError::Limit));
+}
+
+#[test]
+fn allocator() {
+    use super::LARGE_STRING;
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    struct CountingAlloc {
+        allocs: AtomicUsize,
+        deallocs: AtomicUsize,
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self.allocs.fetch_add(1, SeqCst);
+            alloc::alloc::alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.deallocs.fetch_add(1, SeqCst);
+            alloc::alloc::dealloc(ptr, layout)
+        }
+    }
+
+    static COUNTER: CountingAlloc = CountingAlloc {
+        allocs: AtomicUsize::new(0),
+        deallocs: AtomicUsize::new(0),
+    };
+
+    let pool: Pool<4> = PoolConfig::new().allocator(&COUNTER).build();
+    let pool_str = pool.intern("yikes");
+    pool.intern(LARGE_STRING);
+    pool.intern("ryjtyjty");
+    drop(pool);
+
+    // strings keep the pool alive
+    let allocs = COUNTER.allocs.load(SeqCst);
+    assert!(allocs >= 2);
+    assert_eq!(COUNTER.deallocs.load(SeqCst), 0);
+
+    drop(pool_str);
+    assert_eq!(COUNTER.deallocs.load(SeqCst), allocs);
+}
+
+#[test]
+fn hasher() {
+    // FNV-1a, offset by the seed
+    struct Fnv;
+
+    impl PoolHasher for Fnv {
+        fn hash(&self, bytes: &[u8], seed: u64) -> u64 {
+            let mut hash = 0xcbf29ce484222325 ^ seed;
+            for byte in bytes {
+                hash = (hash ^ *byte as u64).wrapping_mul(0x100000001b3);
+            }
+            hash
+        }
+    }
+
+    let pool: Pool<16> = PoolConfig::new().hasher(&Fnv).seed(7).build();
+
+    for string in ["gjnberguieriu", "krjgegyhergyeurgyeyrg", "yikes"] {
+        let pool_str = pool.intern(string);
+        let pool_index = Fnv.hash(string.as_bytes(), 7) as usize % 16;
+        let slot = |len_u8_ref: &u8| core::ptr::eq(len_u8_ref, pool_str.len_ptr);
+        assert!(pool.inner().small_slots(pool_index).any(slot));
+        assert_eq!(pool.find(string), Some(pool_str));
+    }
+}
+
+#[test]
+fn copies_keep_config() {
+    struct Fnv;
+
+    impl PoolHasher for Fnv {
+        fn hash(&self, bytes: &[u8], seed: u64) -> u64 {
+            bytes.iter().fold(0xcbf29ce484222325 ^ seed, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+        }
+    }
+
+    fn tenant(string: &str) -> usize {
+        string[..1].parse().unwrap()
+    }
+
+    let config = PoolConfig::new().seed(7).small_max(8).shard_by(tenant).max_unique(3).hasher(&Fnv);
+    let pool: Pool<4> = config.build();
+    pool.intern("1:yikes");
+
+    let copy = pool.deep_clone();
+    let extracted = pool.extract_subpool(1);
+
+    for inner in [copy.inner().config(), extracted.inner().config()] {
+        assert_eq!((inner.seed, inner.small_max, inner.max_unique), (Some(7), 8, 3));
+        assert_eq!(inner.hasher.hash(b"yikes", 7), Fnv.hash(b"yikes", 7));
+    }
+
+    let pool_str = copy.intern("2:ryjtyjty");
+    let slot = |len_u8_ref: &u8| core::ptr::eq(len_u8_ref, pool_str.len_ptr);
+    assert!(copy.inner().large_slots(2).any(slot));
+
+    assert_eq!(extracted.intern_with_limit("2:a").as_deref(), Ok("2:a"));
+    extracted.intern("3:b");
+    assert_eq!(extracted.intern_with_limit("4:c"), Err(super::InternError::Limit));
+}
+
+#[test]
+fn fallible() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use super::{InternError, test_alloc::FailingAlloc};
+
+    let pool: Pool<4> = PoolConfig::new().allocator(&FailingAlloc).build();
+    let payload = catch_unwind(AssertUnwindSafe(|| pool.intern("yikes"))).unwrap_err();
+    assert_eq!(payload.downcast_ref::<InternError>(), None);
+
+    #[cfg(feature = "std")] {
+        let pool: Pool<4> = PoolConfig::new().allocator(&FailingAlloc).fallible(true).build();
+        let payload = catch_unwind(AssertUnwindSafe(|| pool.intern("yikes"))).unwrap_err();
+        assert_eq!(payload.downcast_ref::<InternError>(), Some(&InternError::Alloc));
+
+        // the pool is left unchanged
+        assert!(pool.is_empty());
+        assert_eq!(&*pool.intern(""), "");
+        assert_eq!(pool.try_intern("yikes"), Err(InternError::Alloc));
+    }
+}
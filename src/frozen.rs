@@ -0,0 +1,95 @@
+use super::{Pool, iter::Strings};
+use alloc::{boxed::Box, vec::Vec, collections::BTreeSet};
+use core::{mem::size_of, str::from_utf8_unchecked, cmp::Ordering};
+
+/// Immutable, flat copy of a pool's strings
+///
+/// All strings are stored contiguously in sorted order, and lookups
+/// are binary searches over their offsets. This doesn't reference
+/// the original pool in any way.
+#[derive(Debug, Clone)]
+pub struct FrozenDict {
+    bytes: Box<[u8]>,
+    // offsets[i]..offsets[i + 1] are the bytes of the i-th string
+    offsets: Box<[usize]>,
+}
+
+impl FrozenDict {
+    fn get(&self, index: usize) -> &str {
+        let range = self.offsets[index]..self.offsets[index + 1];
+        // these bytes were copied from valid strings
+        unsafe { from_utf8_unchecked(&self.bytes[range]) }
+    }
+
+    /// Locates a string in this dictionary
+    pub fn find(&self, string: &str) -> Option<&str> {
+        let (mut low, mut high) = (0, self.len());
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.get(middle).cmp(string) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Some(self.get(middle)),
+            }
+        }
+
+        None
+    }
+
+    /// Number of strings in this dictionary
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Checks if this dictionary contains no string
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bytes allocated by this dictionary
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>() + self.bytes.len() + self.offsets.len() * size_of::<usize>()
+    }
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Copies every string of this pool into a [`FrozenDict`]
+    pub fn freeze_to_boxed_slice(&self) -> FrozenDict {
+        let sorted: BTreeSet<&str> = Strings::new(self.inner()).collect();
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::with_capacity(sorted.len() + 1);
+        offsets.push(0);
+
+        for string in sorted {
+            bytes.extend_from_slice(string.as_bytes());
+            offsets.push(bytes.len());
+        }
+
+        FrozenDict {
+            bytes: bytes.into_boxed_slice(),
+            offsets: offsets.into_boxed_slice(),
+        }
+    }
+}
+
+#[test]
+fn frozen_dict() {
+    use super::LARGE_STRING;
+
+    let pool: Pool<4> = Pool::new();
+    assert!(pool.freeze_to_boxed_slice().is_empty());
+
+    for string in ["ryjtyjty", "gjnberguieriu", LARGE_STRING, "krjgegyhergyeurgyeyrg"] {
+        pool.intern(string);
+    }
+
+    let dict = pool.freeze_to_boxed_slice();
+    assert_eq!(dict.len(), 4);
+    assert_eq!(dict.find("gjnberguieriu"), Some("gjnberguieriu"));
+    assert_eq!(dict.find(LARGE_STRING), Some(LARGE_STRING));
+    assert_eq!(dict.find("yikes"), None);
+    assert_eq!(dict.find(""), None);
+
+    assert!(dict.memory_usage() < pool.memory_usage());
+}
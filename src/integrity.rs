@@ -0,0 +1,112 @@
+use super::{Pool, PoolInner};
+use alloc::vec::Vec;
+
+/// Inconsistency found in the internal structure of a pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityError {
+    /// Index of the subpool
+    pub subpool: usize,
+    /// For small strings, byte offset of the slot in the subpool's
+    /// pages; for large strings, position in the subpool's list
+    pub offset: usize,
+    /// What is wrong
+    pub description: &'static str,
+}
+
+impl core::fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} (subpool {}, offset {})", self.description, self.subpool, self.offset)
+    }
+}
+
+impl<const P: usize, const S: usize> PoolInner<P, S> {
+    pub(crate) fn check(&self, report: &mut dyn FnMut(IntegrityError)) {
+        self.check_pages(report);
+        self.check_large_strings(report);
+    }
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Checks the internal structure of this pool
+    ///
+    /// This is meant to catch memory corruption early;
+    /// the first inconsistency found is returned. It can run
+    /// while other threads intern strings: slots still being
+    /// written are skipped.
+    pub fn verify(&self) -> Result<(), IntegrityError> {
+        let mut first = None;
+        self.inner().check(&mut |error| _ = first.get_or_insert(error));
+
+        match first {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the internal structure of this pool
+    ///
+    /// Unlike [`Pool::verify`], every inconsistency is returned.
+    /// Like it, this can run while the pool is in use.
+    pub fn health_check(&self) -> Result<(), Vec<IntegrityError>> {
+        let mut errors = Vec::new();
+        self.inner().check(&mut |error| errors.push(error));
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}
+
+#[test]
+fn health_check() {
+    use super::LARGE_STRING;
+
+    let pool: Pool<1> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.intern(LARGE_STRING);
+    assert_eq!(pool.health_check(), Ok(()));
+
+    pool.inner().corrupt_first_page(0);
+    pool.inner().corrupt_first_large_string(0);
+
+    let descriptions: Vec<_> = pool.health_check().unwrap_err().iter().map(|error| error.description).collect();
+    assert_eq!(descriptions, [
+        "page header points to another pool",
+        "empty slot marked as not ready",
+        "large string header points to another pool",
+    ]);
+
+    // strings of a corrupted pool can't be freed
+    core::mem::forget(pool);
+}
+
+#[test]
+fn concurrent_health_check() {
+    use alloc::{format, vec::Vec};
+    use core::sync::atomic::{AtomicBool, Ordering::SeqCst};
+
+    let pool: Pool<2, 256> = Pool::new();
+    let strings: Vec<_> = (0..2000).map(|i| format!("string number {}", i)).collect();
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        for offset in 0..4 {
+            let (pool, strings, done) = (&pool, &strings, &done);
+            scope.spawn(move || {
+                for string in strings.iter().skip(offset).step_by(4) {
+                    pool.intern(string);
+                }
+                done.store(true, SeqCst);
+            });
+        }
+
+        while !done.load(SeqCst) {
+            assert_eq!(pool.health_check(), Ok(()));
+            assert_eq!(pool.verify(), Ok(()));
+        }
+    });
+
+    assert_eq!(pool.len(), strings.len());
+    assert_eq!(pool.health_check(), Ok(()));
+}
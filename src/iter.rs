@@ -0,0 +1,68 @@
+use super::{PoolInner, bytes_from_len_u8};
+use core::str::from_utf8;
+use super::{small::SmallSlots, large::LargeSlots};
+
+/// Iterator over the strings of a pool
+///
+/// Byte strings which aren't valid UTF-8 are skipped.
+pub(crate) struct Strings<'a, const P: usize, const S: usize>(Slots<'a, P, S>);
+
+impl<'a, const P: usize, const S: usize> Strings<'a, P, S> {
+    pub(crate) fn new(pool: &'a PoolInner<P, S>) -> Self {
+        Self(Slots::new(pool))
+    }
+}
+
+impl<'a, const P: usize, const S: usize> Iterator for Strings<'a, P, S> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.find_map(|len_u8_ref| from_utf8(bytes_from_len_u8::<P, S>(len_u8_ref)).ok())
+    }
+}
+
+/// Iterator over the length bytes of a pool's strings
+///
+/// Subpools are visited in order; small strings
+/// come before large strings in each subpool.
+pub(crate) struct Slots<'a, const P: usize, const S: usize> {
+    pool: &'a PoolInner<P, S>,
+    pool_index: usize,
+    small: SmallSlots<'a, P, S>,
+    large: LargeSlots<'a, P, S>,
+}
+
+impl<'a, const P: usize, const S: usize> Slots<'a, P, S> {
+    pub(crate) fn new(pool: &'a PoolInner<P, S>) -> Self {
+        Self::subpool(pool, 0)
+    }
+
+    fn subpool(pool: &'a PoolInner<P, S>, pool_index: usize) -> Self {
+        Self {
+            pool,
+            pool_index,
+            small: pool.small_slots(pool_index),
+            large: pool.large_slots(pool_index),
+        }
+    }
+}
+
+impl<'a, const P: usize, const S: usize> Iterator for Slots<'a, P, S> {
+    type Item = &'a u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pool_index < P {
+            if let Some(len_u8_ref) = self.small.next().or_else(|| self.large.next()) {
+                return Some(len_u8_ref);
+            }
+
+            let next_index = self.pool_index + 1;
+            match next_index < P {
+                true => *self = Self::subpool(self.pool, next_index),
+                false => self.pool_index = next_index,
+            }
+        }
+
+        None
+    }
+}
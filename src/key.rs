@@ -0,0 +1,90 @@
+use super::PoolStr;
+use core::{ops::Deref, hash::{Hash, Hasher, BuildHasherDefault}};
+
+/// [`PoolStr`] hashed with the pool's own hash
+///
+/// Its [`Hash`] implementation feeds [`PoolStr::hash_into`] to the
+/// hasher, so large strings aren't read again; small strings are
+/// still hashed, as their pages have no room for a hash. With
+/// [`BuildKeyHasher`], maps keyed by this type don't hash it twice:
+///
+/// ```rust
+/// # use {strpool::{Pool, PoolStrKey, BuildKeyHasher}, std::collections::HashMap};
+/// let pool: Pool<4> = Pool::new();
+/// let mut map = HashMap::with_hasher(BuildKeyHasher::default());
+/// map.insert(PoolStrKey(pool.intern("key")), 1);
+/// assert_eq!(map.get(&PoolStrKey(pool.intern("key"))), Some(&1));
+/// ```
+///
+/// Unlike [`PoolStr`], this can't be looked up with a `&str`, and
+/// keys of a map must come from pools with the same seed and hasher.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PoolStrKey<const P: usize, const S: usize = 1024>(pub PoolStr<P, S>);
+
+impl<const P: usize, const S: usize> Hash for PoolStrKey<P, S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_into(state);
+    }
+}
+
+impl<const P: usize, const S: usize> Deref for PoolStrKey<P, S> {
+    type Target = PoolStr<P, S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const P: usize, const S: usize> From<PoolStr<P, S>> for PoolStrKey<P, S> {
+    fn from(pool_str: PoolStr<P, S>) -> Self {
+        Self(pool_str)
+    }
+}
+
+/// Hasher passing a single `u64` through, for [`PoolStrKey`]
+///
+/// Other inputs are mixed with FNV-1a, so it still works
+/// for keys writing more than one value.
+#[derive(Debug, Clone, Default)]
+pub struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.0 ^= hash;
+    }
+}
+
+/// [`BuildHasher`](core::hash::BuildHasher) of [`KeyHasher`]
+pub type BuildKeyHasher = BuildHasherDefault<KeyHasher>;
+
+#[test]
+fn pool_str_key() {
+    use super::{Pool, LARGE_STRING};
+    use core::hash::BuildHasher;
+
+    let pool: Pool<4> = Pool::new();
+
+    for string in ["yikes", LARGE_STRING] {
+        let key = PoolStrKey(pool.intern(string));
+        let hash = BuildKeyHasher::default().hash_one(&key);
+        assert_eq!(hash, pool.inner().hash(string.as_bytes()));
+        assert_eq!(&**key, string);
+    }
+
+    let mut map = std::collections::HashMap::with_hasher(BuildKeyHasher::default());
+    map.insert(PoolStrKey(pool.intern(LARGE_STRING)), 1);
+    map.insert(pool.intern("yikes").into(), 2);
+    assert_eq!(map.get(&PoolStrKey(pool.intern(LARGE_STRING))), Some(&1));
+    assert_eq!(map.get(&PoolStrKey(pool.intern("yikes"))), Some(&2));
+    assert_eq!(map.get(&PoolStrKey(pool.intern("other"))), None);
+}
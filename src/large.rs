@@ -1,98 +1,111 @@
 use core::sync::atomic::{Ordering::*, AtomicPtr};
-use core::{mem::{size_of, align_of}, ptr::copy};
-use alloc::alloc::{Layout, alloc, dealloc};
+use core::{mem::{size_of, align_of, offset_of}, ptr::copy};
+use alloc::alloc::{Layout, GlobalAlloc};
 
-use super::{PoolInner, PoolStr, hash::hash_str, string_from_len_u8};
+use super::{PoolInner, MaybeStr, InternError, bytes_from_len_u8, integrity::IntegrityError};
 
-const LARGE_STR_ADVANCE: usize = {
-      size_of::<usize>()
-    + size_of::<u64>()
-    + size_of::<*const PoolInner<0>>() // P doesn't influence a pointer's size
-    + size_of::<AtomicPtr<LargeStringHeader<0>>>()
-};
+// P and S don't influence the header's layout
+const LARGE_STR_ADVANCE: usize = offset_of!(LargeStringHeader<0, 0>, len_zero);
 
 #[repr(C)]
 #[derive(Debug)]
-pub(crate) struct LargeStringHeader<const P: usize> {
+pub(crate) struct LargeStringHeader<const P: usize, const S: usize> {
     len: usize,
     hash: u64,
-    pool: *const PoolInner<P>,
-    next: AtomicPtr<LargeStringHeader<P>>,
+    pool: *const PoolInner<P, S>,
+    next: AtomicPtr<LargeStringHeader<P, S>>,
+    char_count: usize,
     len_zero: u8,
 }
 
-impl<const P: usize> PoolInner<P> {
-    pub(crate) fn find_large(&self, string: &str) -> Option<PoolStr<P>> {
-        let hash = hash_str(string);
-        let pool_index = Self::index_from_hash(hash);
-        let mut ptr = self.first_large_string[pool_index].load(Relaxed);
+impl<const P: usize, const S: usize> LargeStringHeader<P, S> {
+    // the hash is checked first as it's cheaper
+    fn matches(&self, bytes: &[u8], hash: u64) -> bool {
+        self.hash == hash
+            && self.len == bytes.len()
+            && bytes_from_len_u8::<P, S>(&self.len_zero) == bytes
+    }
+}
+
+impl<const P: usize, const S: usize> PoolInner<P, S> {
+    pub(crate) fn find_large_slot(&self, bytes: &[u8]) -> Option<&u8> {
+        self.find_large_slot_hashed(bytes, self.hash(bytes))
+    }
+
+    pub(crate) fn find_large_slot_hashed(&self, bytes: &[u8], hash: u64) -> Option<&u8> {
+        let pool_index = self.index_for_hashed(bytes, hash);
+        let mut ptr = self.first_large_string[pool_index].load(Acquire);
 
         while let Some(large_string) = unsafe { ptr.as_ref() } {
-            if large_string.hash == hash {
-                self.inc_ref_count();
-                return Some(PoolStr::new(&large_string.len_zero));
+            if large_string.matches(bytes, hash) {
+                return Some(&large_string.len_zero);
             }
 
-            ptr = large_string.next.load(Relaxed);
+            ptr = large_string.next.load(Acquire);
         }
 
         None
     }
 
-    pub(crate) fn intern_large(&self, string: &str) -> PoolStr<P> {
-        let hash = hash_str(string);
-        let pool_index = Self::index_from_hash(hash);
+    pub(crate) fn intern_large(&self, bytes: &[u8]) -> Result<(&u8, bool), InternError> {
+        self.intern_large_hashed(bytes, self.hash(bytes))
+    }
+
+    pub(crate) fn intern_large_hashed(&self, bytes: &[u8], hash: u64) -> Result<(&u8, bool), InternError> {
+        let pool_index = self.index_for_hashed(bytes, hash);
         let mut ptr = &self.first_large_string[pool_index];
         let mut allocation = None;
 
         loop {
-            while let Some(large_string) = unsafe { ptr.load(Relaxed).as_ref() } {
-                if large_string.hash == hash {
+            while let Some(large_string) = unsafe { ptr.load(Acquire).as_ref() } {
+                if large_string.matches(bytes, hash) {
                     if let Some((new, layout)) = allocation {
-                        unsafe { dealloc(new as _, layout) };
+                        unsafe { self.allocator.dealloc(new as _, layout) };
                     }
                     self.inc_ref_count();
-                    return PoolStr::new(&large_string.len_zero);
+                    return Ok((&large_string.len_zero, false));
                 }
 
                 ptr = &large_string.next;
             }
 
-            let large_string = if let Some((large_string, _)) = allocation {
-                large_string
+            let (large_string, layout) = if let Some(allocation) = allocation {
+                allocation
             } else {
-                let len = string.len();
-                let layout = large_string_layout::<P>(len);
+                let len = bytes.len();
+                let layout = large_string_layout::<P, S>(len);
 
                 let large_string = unsafe {
-                    let ptr = alloc(layout) as *mut LargeStringHeader<P>;
+                    let ptr = self.allocator.alloc(layout) as *mut LargeStringHeader<P, S>;
 
-                    let mut_ref = ptr.as_mut().unwrap();
+                    let mut_ref = ptr.as_mut().ok_or(InternError::Alloc)?;
                     *mut_ref = LargeStringHeader {
                         len,
                         hash,
                         pool: self as _,
                         next: AtomicPtr::new(0 as _),
+                        char_count: char_count(bytes),
                         len_zero: 0,
                     };
 
                     // copy the string bytes
                     let dst = (&mut mut_ref.len_zero as *mut u8).add(1);
-                    copy(string.as_ptr(), dst, len);
+                    copy(bytes.as_ptr(), dst, len);
 
                     ptr
                 };
 
                 allocation = Some((large_string, layout));
 
-                large_string
+                (large_string, layout)
             };
 
             // need to append an entry
-            if ptr.compare_exchange(0 as _, large_string, SeqCst, Relaxed).is_ok() {
+            if ptr.compare_exchange(0 as _, large_string, AcqRel, Acquire).is_ok() {
                 let ls_ref = unsafe { large_string.as_ref() }.unwrap();
+                self.allocated.fetch_add(layout.size(), Relaxed);
                 self.inc_ref_count();
-                break PoolStr::new(&ls_ref.len_zero);
+                break Ok((&ls_ref.len_zero, true));
             }
 
             // if it failed, the search restarts at the
@@ -101,47 +114,166 @@ impl<const P: usize> PoolInner<P> {
         }
     }
 
+    pub(crate) fn large_string_count(&self) -> usize {
+        (0..P).map(|pool_index| self.large_slots(pool_index).count()).sum()
+    }
+
+    pub(crate) fn large_slots(&self, pool_index: usize) -> LargeSlots<'_, P, S> {
+        LargeSlots(unsafe { self.first_large_string[pool_index].load(Acquire).as_ref() })
+    }
+
+    pub(crate) fn check_large_strings(&self, report: &mut dyn FnMut(IntegrityError)) {
+        for pool_index in 0..P {
+            let mut ptr = self.first_large_string[pool_index].load(Acquire);
+            let mut offset = 0;
+
+            while let Some(large_string) = unsafe { ptr.as_ref() } {
+                let mut error = |description| report(IntegrityError {
+                    subpool: pool_index,
+                    offset,
+                    description,
+                });
+
+                if !core::ptr::eq(large_string.pool, self) {
+                    error("large string header points to another pool");
+                }
+
+                if large_string.len_zero != 0 {
+                    error("large string has a non-zero length byte");
+                }
+
+                if large_string.len <= self.small_max {
+                    error("large string is short enough to be small");
+                }
+
+                offset += 1;
+                ptr = large_string.next.load(Acquire);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn corrupt_first_large_string(&self, pool_index: usize) {
+        let ptr = self.first_large_string[pool_index].load(Acquire);
+        let large_string = unsafe { ptr.as_mut() }.unwrap();
+        large_string.pool = core::ptr::null();
+    }
+
     pub(crate) fn debug_large_strings(&self, output: &mut core::fmt::DebugList) {
         for pool_index in 0..P {
-            let mut ptr = self.first_large_string[pool_index].load(Relaxed);
+            let mut ptr = self.first_large_string[pool_index].load(Acquire);
 
             while let Some(large_string) = unsafe { ptr.as_ref() } {
-                let string = string_from_len_u8::<P>(&large_string.len_zero);
-                output.entry(&string);
-                ptr = large_string.next.load(Relaxed);
+                let bytes = bytes_from_len_u8::<P, S>(&large_string.len_zero);
+                output.entry(&MaybeStr(bytes));
+                ptr = large_string.next.load(Acquire);
             }
         }
     }
 }
 
-fn large_string_layout<const P: usize>(len: usize) -> Layout {
-    // this currently wastes 3-7 bytes (todo)
-    let size = size_of::<LargeStringHeader<P>>() + len;
+/// Iterator over the strings of a subpool's large string list
+pub(crate) struct LargeSlots<'a, const P: usize, const S: usize>(Option<&'a LargeStringHeader<P, S>>);
+
+impl<'a, const P: usize, const S: usize> Iterator for LargeSlots<'a, P, S> {
+    type Item = &'a u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let large_string = self.0?;
+        self.0 = unsafe { large_string.next.load(Acquire).as_ref() };
+        Some(&large_string.len_zero)
+    }
+}
+
+// same as str::chars().count() for valid UTF-8:
+// counts the bytes which aren't continuation bytes
+fn char_count(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|byte| (**byte as i8) >= -0x40).count()
+}
+
+pub(crate) fn large_string_layout<const P: usize, const S: usize>(len: usize) -> Layout {
+    // the bytes follow len_zero, in the padding at the end of
+    // the header; the header itself must still fit entirely.
+    let size = (LARGE_STR_ADVANCE + 1 + len).max(size_of::<LargeStringHeader<P, S>>());
     Layout::from_size_align(size, align_of::<usize>()).unwrap()
 }
 
-fn get_large_string<const P: usize>(len_u8_ptr: &u8) -> &LargeStringHeader<P> {
+fn get_large_string<const P: usize, const S: usize>(len_u8_ptr: &u8) -> &LargeStringHeader<P, S> {
     unsafe {
         (len_u8_ptr as *const u8)
             .sub(LARGE_STR_ADVANCE)
-            .cast::<LargeStringHeader<P>>()
+            .cast::<LargeStringHeader<P, S>>()
             .as_ref()
             .unwrap()
     }
 }
 
-pub(crate) fn string_pool_ptr<const P: usize>(len_u8_ptr: &u8) -> *const PoolInner<P> {
+#[cfg(feature = "debug-internals")]
+pub(crate) fn header_ptr<const P: usize, const S: usize>(len_u8_ptr: &u8) -> *const () {
+    get_large_string::<P, S>(len_u8_ptr) as *const _ as *const ()
+}
+
+pub(crate) fn string_pool_ptr<const P: usize, const S: usize>(len_u8_ptr: &u8) -> *const PoolInner<P, S> {
     get_large_string(len_u8_ptr).pool
 }
 
-pub(crate) fn read_actual_string_len<const P: usize>(len_u8_ptr: &u8) -> usize {
-    get_large_string::<P>(len_u8_ptr).len
+pub(crate) fn read_actual_string_len<const P: usize, const S: usize>(len_u8_ptr: &u8) -> usize {
+    get_large_string::<P, S>(len_u8_ptr).len
+}
+
+pub(crate) fn read_hash<const P: usize, const S: usize>(len_u8_ptr: &u8) -> u64 {
+    get_large_string::<P, S>(len_u8_ptr).hash
 }
 
-pub(crate) fn deep_drop<const P: usize>(mut ptr: *const LargeStringHeader<P>) {
+pub(crate) fn read_char_count<const P: usize, const S: usize>(len_u8_ptr: &u8) -> usize {
+    get_large_string::<P, S>(len_u8_ptr).char_count
+}
+
+pub(crate) fn deep_drop<const P: usize, const S: usize>(mut ptr: *const LargeStringHeader<P, S>, allocator: &dyn GlobalAlloc) {
     while let Some(large_string) = unsafe { ptr.as_ref() } {
         let mut_ptr = (ptr as usize) as *mut u8;
         ptr = large_string.next.load(Relaxed);
-        unsafe { dealloc(mut_ptr, large_string_layout::<P>(large_string.len)) };
+        unsafe { allocator.dealloc(mut_ptr, large_string_layout::<P, S>(large_string.len)) };
     }
 }
+
+#[test]
+fn hash_collision() {
+    use super::{Pool, PoolStr, LARGE_STRING};
+    use alloc::format;
+
+    let (a, b) = (format!("{}a", LARGE_STRING), format!("{}b", LARGE_STRING));
+
+    // both strings end up in the same list with the same hash
+    let pool: Pool<4> = Pool::new();
+    let inner = pool.inner();
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let hash = inner.hash(a);
+
+    let (slot_a, new_a) = inner.intern_large_hashed(a, hash).unwrap();
+    let (slot_b, new_b) = inner.intern_large_hashed(b, hash).unwrap();
+    let slots = [slot_a, slot_b].map(PoolStr::<4>::new);
+    assert!(new_a && new_b);
+    assert_eq!((slots[0].as_bytes(), slots[1].as_bytes()), (a, b));
+    assert_eq!(inner.large_string_count(), 2);
+
+    assert_eq!(inner.find_large_slot_hashed(a, hash).map(bytes_from_len_u8::<4, 1024>), Some(a));
+    assert_eq!(inner.find_large_slot_hashed(b, hash).map(bytes_from_len_u8::<4, 1024>), Some(b));
+    let (slot, new) = inner.intern_large_hashed(b, hash).unwrap();
+    assert!(core::ptr::eq(slot, slot_b) && !new);
+    drop(PoolStr::<4>::new(slot));
+}
+
+#[test]
+fn large_string_size() {
+    let header = size_of::<LargeStringHeader<4, 1024>>();
+    assert!(LARGE_STR_ADVANCE + 1 < header);
+
+    for len in [127, 128, 1000] {
+        let layout = large_string_layout::<4, 1024>(len);
+        assert_eq!(layout.size(), LARGE_STR_ADVANCE + 1 + len);
+    }
+
+    // very short large strings, with a lowered small_max
+    assert_eq!(large_string_layout::<4, 1024>(1).size(), header);
+}
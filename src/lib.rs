@@ -7,59 +7,162 @@ extern crate std;
 extern crate alloc;
 
 use core::sync::atomic::{Ordering::*, AtomicPtr, AtomicUsize};
-use core::{slice::from_raw_parts, str::from_utf8, ops::Deref};
-use alloc::boxed::Box;
+use core::{mem::size_of, slice::from_raw_parts, str::from_utf8, ops::Deref, ptr::{null, null_mut}};
+use alloc::{boxed::Box, collections::BTreeSet, alloc::GlobalAlloc};
 
 mod hash;
 mod small;
 mod large;
 mod traits;
+mod iter;
+mod tiered;
+mod normalize;
+mod bulk;
+mod frozen;
+mod search;
+mod config;
+mod combinators;
+mod integrity;
+mod symbol;
+mod postings;
+mod bytes;
+mod key;
+
+#[cfg(feature = "debug-internals")]
+mod location;
+
+#[cfg(test)]
+mod test_alloc;
 
 #[cfg(feature = "std")]
 mod static_pool;
 
+pub use tiered::TieredPool;
+pub use frozen::FrozenDict;
+pub use config::{PoolConfig, SMALL_MAX};
+pub use bulk::{ProbeStats, AllocEvent, AllocTier, Stage};
+pub use integrity::IntegrityError;
+pub use normalize::{NonAsciiError, Escaper, ParseNumberError};
+pub use symbol::{Symbol, SymbolMap, InternPipeline};
+pub use postings::InvertedIndex;
+pub use bytes::PoolBytes;
+pub use hash::PoolHasher;
+pub use key::{PoolStrKey, KeyHasher, BuildKeyHasher};
+
 #[cfg(feature = "std")]
-pub use static_pool::PoolCell;
+pub use static_pool::{PoolCell, OncePoolCell};
+
+#[cfg(feature = "debug-internals")]
+pub use location::DebugLocation;
 
 #[cfg(feature = "serde")]
 pub mod serde;
 
-struct PoolInner<const P: usize> {
+struct PoolInner<const P: usize, const S: usize> {
     ref_count: AtomicUsize,
-    first_page: [AtomicPtr<small::Page<P>>; P],
-    first_large_string: [AtomicPtr<large::LargeStringHeader<P>>; P],
+    seed: u64,
+    small_max: usize,
+    shard: Option<fn(&str) -> usize>,
+    max_unique: usize,
+    allocator: &'static (dyn GlobalAlloc + Sync),
+    hasher: &'static dyn PoolHasher,
+    // stored strings, plus the ones being interned with a limit
+    unique: AtomicUsize,
+    // bytes of the pages and large strings
+    allocated: AtomicUsize,
+    fallible: bool,
+    first_page: [AtomicPtr<small::Page<P, S>>; P],
+    first_large_string: [AtomicPtr<large::LargeStringHeader<P, S>>; P],
 }
 
 /// String pool
-pub struct Pool<const P: usize = 1> {
-    inner: *const PoolInner<P>,
+///
+/// `P` is the number of subpools, and `S` the size in bytes of the
+/// pages holding small strings. Both must be powers of two.
+pub struct Pool<const P: usize = 1, const S: usize = 1024> {
+    inner: *const PoolInner<P, S>,
+}
+
+/// Error returned by [`Pool::try_intern`] and [`Pool::intern_with_limit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InternError {
+    /// Memory for a new page or large string couldn't be allocated
+    Alloc,
+    /// The pool already holds [`PoolConfig::max_unique`] strings
+    Limit,
+}
+
+impl core::fmt::Display for InternError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Alloc => write!(f, "memory allocation failed"),
+            Self::Limit => write!(f, "unique string limit reached"),
+        }
+    }
 }
 
 /// `&str` equivalent
-pub struct PoolStr<const P: usize> {
+pub struct PoolStr<const P: usize, const S: usize = 1024> {
     len_ptr: *const u8,
     _phantom: [(); P],
 }
 
-impl<const P: usize> PoolInner<P> {
-    const FIRST_PAGE_NEW: AtomicPtr<small::Page<P>> = AtomicPtr::new(0 as _);
-    const FIRST_LS_NEW: AtomicPtr<large::LargeStringHeader<P>> = AtomicPtr::new(0 as _);
-    const NEW: Self = Self {
-        ref_count: AtomicUsize::new(1),
-        first_page: [Self::FIRST_PAGE_NEW; P],
-        first_large_string: [Self::FIRST_LS_NEW; P],
-    };
+impl<const P: usize, const S: usize> PoolInner<P, S> {
+    // evaluated at compile time when a pool is created
+    const VALID_P: () = assert!(P.is_power_of_two(), "the number of subpools must be a power of two");
+
+    fn new(config: &PoolConfig) -> Self {
+        Self {
+            ref_count: AtomicUsize::new(1),
+            seed: config.seed.unwrap_or(hash::SEED_U64),
+            small_max: config.small_max,
+            shard: config.shard,
+            max_unique: config.max_unique,
+            allocator: config.allocator,
+            hasher: config.hasher,
+            unique: AtomicUsize::new(0),
+            allocated: AtomicUsize::new(0),
+            fallible: config.fallible,
+            first_page: [const { AtomicPtr::new(null_mut()) }; P],
+            first_large_string: [const { AtomicPtr::new(null_mut()) }; P],
+        }
+    }
+
+    // the settings this pool was created with
+    fn config(&self) -> PoolConfig {
+        PoolConfig {
+            seed: Some(self.seed),
+            small_max: self.small_max,
+            shard: self.shard,
+            max_unique: self.max_unique,
+            allocator: self.allocator,
+            hasher: self.hasher,
+            fallible: self.fallible,
+        }
+    }
 
     fn index_from_hash(hash: u64) -> usize {
-        assert!(P.is_power_of_two());
         (hash as usize) & (P - 1)
     }
 
-    fn index_for(string: &str) -> usize {
-        match P {
-            0 => 0,
-            _ => Self::index_from_hash(hash::hash_str(string)),
-        }
+    fn hash(&self, bytes: &[u8]) -> u64 {
+        self.hasher.hash(bytes, self.seed)
+    }
+
+    // the shard function only applies to valid UTF-8
+    fn shard_of(&self, bytes: &[u8]) -> Option<usize> {
+        let shard = self.shard?;
+        let string = from_utf8(bytes).ok()?;
+        Some(shard(string) % P)
+    }
+
+    fn index_for(&self, bytes: &[u8]) -> usize {
+        self.shard_of(bytes).unwrap_or_else(|| Self::index_from_hash(self.hash(bytes)))
+    }
+
+    // same as index_for, when the hash is already known
+    fn index_for_hashed(&self, bytes: &[u8], hash: u64) -> usize {
+        self.shard_of(bytes).unwrap_or_else(|| Self::index_from_hash(hash))
     }
 
     fn inc_ref_count(&self) {
@@ -71,30 +174,159 @@ impl<const P: usize> PoolInner<P> {
         self.ref_count.fetch_sub(1, SeqCst) == 1
     }
 
-    fn find(&self, string: &str) -> Option<PoolStr<P>> {
-        match string.len() {
-            0 => Some(PoolStr::empty()),
-            1..=126 => self.find_small(string),
-            _ => self.find_large(string),
+    fn is_small(&self, bytes: &[u8]) -> bool {
+        (1..=self.small_max).contains(&bytes.len())
+    }
+
+    // doesn't touch ref_count; the empty string isn't stored
+    fn find_slot(&self, bytes: &[u8]) -> Option<&u8> {
+        match bytes.len() {
+            0 => None,
+            _ if self.is_small(bytes) => self.find_small_slot(bytes),
+            _ => self.find_large_slot(bytes),
+        }
+    }
+
+    // same as find_slot, when the hash is already known
+    fn find_slot_hashed(&self, bytes: &[u8], hash: u64) -> Option<&u8> {
+        match bytes.len() {
+            0 => None,
+            _ if self.is_small(bytes) => self.find_small_slot_hashed(bytes, hash),
+            _ => self.find_large_slot_hashed(bytes, hash),
+        }
+    }
+
+    fn find(&self, string: &str) -> Option<PoolStr<P, S>> {
+        if string.is_empty() {
+            return Some(PoolStr::empty());
+        }
+
+        let len_u8_ref = self.find_slot(string.as_bytes())?;
+        self.inc_ref_count();
+        Some(PoolStr::new(len_u8_ref))
+    }
+
+    // doesn't touch ref_count
+    fn contains(&self, string: &str) -> bool {
+        string.is_empty() || self.find_slot(string.as_bytes()).is_some()
+    }
+
+    // doesn't touch the unique count
+    fn store_slot(&self, bytes: &[u8]) -> Result<Option<(&u8, bool)>, InternError> {
+        match bytes.len() {
+            0 => Ok(None),
+            _ if self.is_small(bytes) => self.intern_small(bytes).map(Some),
+            _ => self.intern_large(bytes).map(Some),
+        }
+    }
+
+    // the boolean is true if the bytes were newly stored;
+    // the returned slot is accounted for in ref_count
+    fn get_or_intern_slot(&self, bytes: &[u8]) -> Result<Option<(&u8, bool)>, InternError> {
+        let slot = self.store_slot(bytes)?;
+
+        if let Some((_, true)) = slot {
+            self.unique.fetch_add(1, SeqCst);
+        }
+
+        Ok(slot)
+    }
+
+    // same as get_or_intern_slot, when the hash is already known
+    fn get_or_intern_slot_hashed(&self, bytes: &[u8], hash: u64) -> Result<Option<(&u8, bool)>, InternError> {
+        let slot = match bytes.len() {
+            0 => None,
+            _ if self.is_small(bytes) => Some(self.intern_small_hashed(bytes, hash)?),
+            _ => Some(self.intern_large_hashed(bytes, hash)?),
+        };
+
+        if let Some((_, true)) = slot {
+            self.unique.fetch_add(1, SeqCst);
+        }
+
+        Ok(slot)
+    }
+
+    fn intern_with_limit(&self, string: &str) -> Result<PoolStr<P, S>, InternError> {
+        if let Some(pool_str) = self.find(string) {
+            return Ok(pool_str);
+        }
+
+        // reserve a place for the string before storing it
+        let max_unique = self.max_unique;
+        let reserve = |unique| (unique < max_unique).then_some(unique + 1);
+        if self.unique.fetch_update(SeqCst, SeqCst, reserve).is_err() {
+            return Err(InternError::Limit);
+        }
+
+        let slot = self.store_slot(string.as_bytes());
+
+        if !matches!(slot, Ok(Some((_, true)))) {
+            // another thread interned it in the meantime, or allocation failed
+            self.unique.fetch_sub(1, SeqCst);
+        }
+
+        match slot? {
+            Some((len_u8_ref, _)) => Ok(PoolStr::new(len_u8_ref)),
+            None => Ok(PoolStr::empty()),
+        }
+    }
+
+    // the boolean is true if the string was newly stored
+    fn get_or_intern(&self, string: &str) -> Result<(PoolStr<P, S>, bool), InternError> {
+        match self.get_or_intern_slot(string.as_bytes())? {
+            Some((len_u8_ref, new)) => Ok((PoolStr::new(len_u8_ref), new)),
+            None => Ok((PoolStr::empty(), false)),
         }
     }
 
-    fn intern(&self, string: &str) -> PoolStr<P> {
-        match string.len() {
-            0 => PoolStr::empty(),
-            1..=126 => self.intern_small(string),
-            _ => self.intern_large(string),
+    fn try_intern(&self, string: &str) -> Result<PoolStr<P, S>, InternError> {
+        self.get_or_intern(string).map(|(pool_str, _)| pool_str)
+    }
+
+    fn intern(&self, string: &str) -> PoolStr<P, S> {
+        self.interned(self.try_intern(string))
+    }
+
+    // panics on errors, see PoolConfig::fallible
+    fn interned<T>(&self, result: Result<T, InternError>) -> T {
+        match result {
+            Ok(value) => value,
+            #[cfg(feature = "std")]
+            Err(error) if self.fallible => std::panic::panic_any(error),
+            Err(error) => panic!("intern: {}", error),
+        }
+    }
+
+    // `hash` must be the hash of `string`
+    fn intern_hashed(&self, string: &str, hash: u64) -> PoolStr<P, S> {
+        match self.interned(self.get_or_intern_slot_hashed(string.as_bytes(), hash)) {
+            Some((len_u8_ref, _)) => PoolStr::new(len_u8_ref),
+            None => PoolStr::empty(),
         }
     }
 }
 
-impl<const P: usize> Pool<P> {
+impl<const P: usize, const S: usize> Pool<P, S> {
     /// Creates a new pool
     pub fn new() -> Self {
-        assert!(P.is_power_of_two());
+        Self::with_config(PoolConfig::new())
+    }
+
+    /// Creates a new pool with custom settings
+    ///
+    /// `P` and `S` are checked at compile time:
+    ///
+    /// ```compile_fail
+    /// # use strpool::Pool;
+    /// let pool: Pool<3> = Pool::new();
+    /// ```
+    pub fn with_config(config: PoolConfig) -> Self {
+        let () = PoolInner::<P, S>::VALID_P;
+        let () = small::Page::<P, S>::VALID_S;
 
         // ref_count is set to one in each inner pool
-        let boxed = Box::new(PoolInner::NEW);
+        let boxed = Box::new(PoolInner::new(&config));
         Self {
             inner: Box::into_raw(boxed),
         }
@@ -106,22 +338,251 @@ impl<const P: usize> Pool<P> {
         Self::new()
     }
 
-    fn inner(&self) -> &PoolInner<P> {
+    /// Converts this handle into an opaque pointer, e.g. for FFI
+    ///
+    /// The reference held by this handle is transferred to the
+    /// pointer; use [`Pool::from_inner_ptr`] to get it back.
+    pub fn into_inner_ptr(self) -> *const () {
+        let inner = self.inner;
+        core::mem::forget(self);
+        inner as *const ()
+    }
+
+    /// Re-creates a handle from a pointer returned by [`Pool::into_inner_ptr`]
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must come from `Pool::<P, S>::into_inner_ptr` (with the same `P`)
+    /// and must be passed to this function only once.
+    pub unsafe fn from_inner_ptr(ptr: *const ()) -> Self {
+        Self {
+            inner: ptr as *const PoolInner<P, S>,
+        }
+    }
+
+    fn inner(&self) -> &PoolInner<P, S> {
         unsafe { self.inner.as_ref() }.unwrap()
     }
 
     /// Locates an existing [`PoolStr`]
-    pub fn find(&self, string: &str) -> Option<PoolStr<P>> {
+    ///
+    /// This never allocates and only updates the reference count of the
+    /// pool. A string being interned by another thread at the same time
+    /// may not be found yet.
+    pub fn find(&self, string: &str) -> Option<PoolStr<P, S>> {
         self.inner().find(string)
     }
 
+    /// Borrows the contents of `pool_str` for as long as this pool
+    ///
+    /// Strings are never moved or freed before their pool is dropped,
+    /// so the result can outlive `pool_str`. This returns `None` if
+    /// `pool_str` belongs to another pool.
+    pub fn as_str<'a>(&'a self, pool_str: &PoolStr<P, S>) -> Option<&'a str> {
+        match pool_str.pool_ptr() {
+            None => Some(""),
+            Some(pool_ptr) if core::ptr::eq(pool_ptr, self.inner) => {
+                // the storage of this pool outlives 'a
+                let len_u8_ref: &'a u8 = unsafe { &*pool_str.len_ptr };
+                Some(string_from_len_u8::<P, S>(len_u8_ref))
+            },
+            Some(_) => None,
+        }
+    }
+
+    /// Checks if `string` is in this pool
+    ///
+    /// Unlike [`Pool::find`], this doesn't update the reference
+    /// count. The empty string is always considered present.
+    pub fn contains(&self, string: &str) -> bool {
+        self.inner().contains(string)
+    }
+
     /// Creates a new [`PoolStr`]
-    pub fn intern(&self, string: &str) -> PoolStr<P> {
+    pub fn intern(&self, string: &str) -> PoolStr<P, S> {
         self.inner().intern(string)
     }
+
+    /// Creates a new [`PoolStr`], failing if memory can't be allocated
+    ///
+    /// The pool is left unchanged when this fails.
+    pub fn try_intern(&self, string: &str) -> Result<PoolStr<P, S>, InternError> {
+        self.inner().try_intern(string)
+    }
+
+    /// Creates a new [`PoolStr`] if the unique string limit allows it
+    ///
+    /// This returns [`InternError::Limit`] if `string` isn't in the pool
+    /// and the pool already holds [`PoolConfig::max_unique`] strings, and
+    /// [`InternError::Alloc`] if memory can't be allocated. Existing strings
+    /// are always returned. Other methods ignore this limit, so pools
+    /// exposed to untrusted input should only be filled through this.
+    pub fn intern_with_limit(&self, string: &str) -> Result<PoolStr<P, S>, InternError> {
+        self.inner().intern_with_limit(string)
+    }
+
+    /// Creates two [`PoolStr`], stored next to each other if possible
+    ///
+    /// This improves locality for strings that are always used together.
+    /// It only applies when both strings are new, distinct, short enough
+    /// to be small strings and when they belong to the same subpool.
+    pub fn intern_adjacent(&self, a: &str, b: &str) -> (PoolStr<P, S>, PoolStr<P, S>) {
+        let inner = self.inner();
+        let (a_bytes, b_bytes) = (a.as_bytes(), b.as_bytes());
+        let small = |bytes| inner.is_small(bytes);
+        let adjacent = small(a_bytes) && small(b_bytes) && a != b && inner.index_for(a_bytes) == inner.index_for(b_bytes);
+
+        match (self.find(a), self.find(b)) {
+            (None, None) if adjacent => inner.interned(inner.intern_small_pair(a, b)),
+            (a_str, b_str) => (
+                a_str.unwrap_or_else(|| self.intern(a)),
+                b_str.unwrap_or_else(|| self.intern(b)),
+            ),
+        }
+    }
+
+    /// Creates a new [`PoolStr`], calling `on_new` if it wasn't in the pool
+    ///
+    /// `on_new` isn't called for the empty string.
+    pub fn intern_observing<F: FnOnce(&PoolStr<P, S>)>(&self, string: &str, on_new: F) -> PoolStr<P, S> {
+        let inner = self.inner();
+        let (pool_str, new) = inner.interned(inner.get_or_intern(string));
+
+        if new {
+            on_new(&pool_str);
+        }
+
+        pool_str
+    }
+
+    /// Creates an independent pool holding the same strings
+    ///
+    /// Unlike [`Clone`], the new pool shares no memory with this one.
+    /// It is created with the same [`PoolConfig`].
+    pub fn deep_clone(&self) -> Self {
+        let pool = Self::with_config(self.inner().config());
+
+        for len_u8_ref in iter::Slots::new(self.inner()) {
+            pool.intern_bytes(bytes_from_len_u8::<P, S>(len_u8_ref));
+        }
+
+        pool
+    }
+
+    /// Creates an independent pool holding the strings of one subpool
+    ///
+    /// The new pool is created with the same [`PoolConfig`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` isn't lower than `P`.
+    pub fn extract_subpool(&self, index: usize) -> Pool<1, S> {
+        assert!(index < P, "extract_subpool: no subpool at index {}", index);
+        let inner = self.inner();
+        let pool = Pool::with_config(inner.config());
+
+        for len_u8_ref in inner.small_slots(index).chain(inner.large_slots(index)) {
+            pool.intern_bytes(bytes_from_len_u8::<P, S>(len_u8_ref));
+        }
+
+        pool
+    }
+
+    /// Number of unique strings in this pool
+    ///
+    /// This traverses the whole pool and includes byte strings.
+    /// The empty string isn't stored, so it isn't counted.
+    pub fn len(&self) -> usize {
+        iter::Slots::new(self.inner()).count()
+    }
+
+    /// Checks if no string was interned in this pool
+    pub fn is_empty(&self) -> bool {
+        let inner = self.inner();
+        let no_page = inner.first_page.iter().all(|ptr| ptr.load(Relaxed).is_null());
+        let no_large_string = inner.first_large_string.iter().all(|ptr| ptr.load(Relaxed).is_null());
+        no_page && no_large_string
+    }
+
+    /// Iterates over the strings of this pool
+    ///
+    /// Byte strings which aren't valid UTF-8 are skipped. Each string
+    /// is visited once, in no particular order. Strings interned
+    /// during the iteration may or may not be visited.
+    pub fn iter(&self) -> impl Iterator<Item = &str> + '_ {
+        iter::Strings::new(self.inner())
+    }
+
+    /// Iterates over the strings of this pool, as [`PoolStr`]s
+    ///
+    /// See [`Pool::iter`].
+    pub fn iter_pool_str(&self) -> impl Iterator<Item = PoolStr<P, S>> + '_ {
+        let inner = self.inner();
+
+        iter::Slots::new(inner).filter(|len_u8_ref| {
+            from_utf8(bytes_from_len_u8::<P, S>(len_u8_ref)).is_ok()
+        }).map(move |len_u8_ref| {
+            inner.inc_ref_count();
+            PoolStr::new(len_u8_ref)
+        })
+    }
+
+    /// Number of bytes allocated by this pool
+    ///
+    /// This is tracked while interning, so it doesn't traverse the pool.
+    pub fn memory_usage(&self) -> usize {
+        size_of::<PoolInner<P, S>>() + self.inner().allocated.load(Relaxed)
+    }
+
+    /// Sum of the lengths of the strings in this pool
+    ///
+    /// This traverses the whole pool, like [`Pool::len`].
+    pub fn total_string_bytes(&self) -> usize {
+        iter::Slots::new(self.inner()).map(|len_u8_ref| bytes_from_len_u8::<P, S>(len_u8_ref).len()).sum()
+    }
+
+    /// Average length of the strings in this pool
+    ///
+    /// This is zero for an empty pool.
+    pub fn average_length(&self) -> f64 {
+        let (mut count, mut bytes) = (0, 0);
+
+        for len_u8_ref in iter::Slots::new(self.inner()) {
+            count += 1;
+            bytes += bytes_from_len_u8::<P, S>(len_u8_ref).len();
+        }
+
+        match count {
+            0 => 0.0,
+            _ => bytes as f64 / count as f64,
+        }
+    }
+
+    /// Counts the extra copies of strings stored more than once
+    ///
+    /// Concurrent interns of the same string can, rarely, store
+    /// it twice. This returns the number of stored strings minus
+    /// the number of unique strings.
+    pub fn audit_duplicates(&self) -> usize {
+        let mut unique = BTreeSet::new();
+        let mut stored = 0;
+
+        for len_u8_ref in iter::Slots::new(self.inner()) {
+            unique.insert(bytes_from_len_u8::<P, S>(len_u8_ref));
+            stored += 1;
+        }
+
+        stored - unique.len()
+    }
+}
+
+impl<const P: usize, const S: usize> Default for Pool<P, S> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<const P: usize> PoolStr<P> {
+impl<const P: usize, const S: usize> PoolStr<P, S> {
     fn new(len: &u8) -> Self {
         Self {
             len_ptr: len as *const u8,
@@ -131,71 +592,249 @@ impl<const P: usize> PoolStr<P> {
 
     pub fn empty() -> Self {
         Self {
-            len_ptr: 0 as *const u8,
+            len_ptr: null(),
             _phantom: [(); P],
         }
     }
 
-    fn pool_ptr(&self) -> Option<*const PoolInner<P>> {
-        let len = unsafe { self.len_ptr.as_ref()? };
+    fn pool_ptr(&self) -> Option<*const PoolInner<P, S>> {
+        pool_ptr_of(self.len_ptr)
+    }
+
+    /// Returns a handle to the pool of this string
+    ///
+    /// The empty string belongs to no pool.
+    pub fn pool(&self) -> Option<Pool<P, S>> {
+        let inner = self.pool_ptr()?;
+        self.pool_inner()?.inc_ref_count();
+        Some(Pool { inner })
+    }
+
+    fn pool_inner(&self) -> Option<&PoolInner<P, S>> {
+        // the pool outlives its strings
+        self.pool_ptr().map(|pool_ptr| unsafe { &*pool_ptr })
+    }
+
+    /// Bytes of this string
+    ///
+    /// Unlike [`Deref`], this doesn't validate them as UTF-8 again.
+    pub fn as_bytes(&self) -> &[u8] {
+        match unsafe { self.len_ptr.as_ref() } {
+            Some(len_u8_ptr) => bytes_from_len_u8::<P, S>(len_u8_ptr),
+            None => &[],
+        }
+    }
+
+    /// Contents of this string
+    ///
+    /// To borrow them beyond the lifetime of this handle,
+    /// see [`Pool::as_str`].
+    pub fn as_str(&self) -> &str {
+        self
+    }
+
+    /// Contents of this string, borrowed as a [`Cow`](alloc::borrow::Cow)
+    ///
+    /// This never copies the string.
+    pub fn as_cow(&self) -> alloc::borrow::Cow<'_, str> {
+        alloc::borrow::Cow::Borrowed(self)
+    }
+
+    /// Length of this string, in bytes
+    ///
+    /// This is stored in the header of large strings.
+    pub fn len(&self) -> usize {
+        match unsafe { self.len_ptr.as_ref() } {
+            Some(len_u8_ref @ 0) => large::read_actual_string_len::<P, S>(len_u8_ref),
+            Some(len) => *len as usize,
+            None => 0,
+        }
+    }
+
+    /// Checks if this is the empty string
+    pub fn is_empty(&self) -> bool {
+        self.len_ptr.is_null()
+    }
+
+    /// Compares the bytes of two strings without re-validating them
+    ///
+    /// This is what the [`PartialEq`] implementation uses.
+    pub fn bytes_eq(&self, other: &Self) -> bool {
+           self.len_ptr == other.len_ptr
+        || self.as_bytes() == other.as_bytes()
+    }
+
+    /// Locale-independent sort key
+    ///
+    /// These are the UTF-8 bytes of the string, which sort in the
+    /// same order as Unicode scalar values (and as `str::cmp`).
+    pub fn sort_key_bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+
+    /// Checks if all chars of this string are ASCII
+    ///
+    /// This is cached for large strings.
+    pub fn is_ascii(&self) -> bool {
+        match unsafe { self.len_ptr.as_ref() } {
+            Some(0) => self.char_count() == self.as_bytes().len(),
+            _ => self.as_bytes().is_ascii(),
+        }
+    }
+
+    /// Number of chars in this string
+    ///
+    /// This is cached for large strings.
+    pub fn char_count(&self) -> usize {
+        match unsafe { self.len_ptr.as_ref() } {
+            Some(len_u8_ref @ 0) => large::read_char_count::<P, S>(len_u8_ref),
+            _ => self.chars().count(),
+        }
+    }
+
+    /// Copies the bytes of this string into an array
+    ///
+    /// This returns the array and the number of bytes used in it,
+    /// the rest being zeroes, or `None` if this string doesn't fit.
+    pub fn copy_to_array<const N: usize>(&self) -> Option<([u8; N], usize)> {
+        let bytes = self.as_bytes();
+        let mut array = [0; N];
+        array.get_mut(..bytes.len())?.copy_from_slice(bytes);
+        Some((array, bytes.len()))
+    }
+
+    /// Returns the byte at index `i`, if any
+    pub fn byte_at(&self, i: usize) -> Option<u8> {
+        self.as_bytes().get(i).copied()
+    }
+
+    /// Returns the char at index `char_index`, if any
+    ///
+    /// This counts chars from the start of the string.
+    pub fn char_at(&self, char_index: usize) -> Option<char> {
+        self.chars().nth(char_index)
+    }
+
+    /// Returns the char of this string if it has exactly one
+    pub fn as_char(&self) -> Option<char> {
+        if self.as_bytes().len() > 4 {
+            return None;
+        }
+
+        let mut chars = self.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
 
-        let pool_ptr = match *len {
-            0 => large::string_pool_ptr(len),
-            _ => small::string_pool_ptr(len),
+    /// Feeds the pool's 64-bit hash of this string into `state`
+    ///
+    /// Large strings store this hash, so their bytes aren't read again.
+    /// This differs from the [`Hash`](core::hash::Hash) implementation,
+    /// and depends on the seed of the pool.
+    pub fn hash_into<H: core::hash::Hasher>(&self, state: &mut H) {
+        let hash = match unsafe { self.len_ptr.as_ref() } {
+            Some(len_u8_ref @ 0) => large::read_hash::<P, S>(len_u8_ref),
+            Some(_) => self.pool_inner().unwrap().hash(self.as_bytes()),
+            None => 0,
         };
 
-        Some(pool_ptr)
+        state.write_u64(hash);
+    }
+
+    /// Writes the bytes of this string to `writer`
+    #[cfg(feature = "std")]
+    pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(self.as_bytes())
+    }
+
+    /// Raw slot of a small string: its length byte followed by its bytes
+    ///
+    /// This is an advanced API exposing the encoding of the small-string
+    /// tier (strings of 1 to 126 bytes by default). Large strings and the empty
+    /// string aren't stored in such slots, so `None` is returned for them.
+    pub fn raw_slot(&self) -> Option<&[u8]> {
+        let len = unsafe { self.len_ptr.as_ref()? };
+
+        match *len {
+            0 => None,
+            l => Some(unsafe { from_raw_parts(self.len_ptr, 1 + l as usize) }),
+        }
     }
 }
 
 // this function assumes that len_u8_ref points
 // to a finished/ready slot, for small strings
-fn string_from_len_u8<const P: usize>(len_u8_ref: &u8) -> &str {
+fn bytes_from_len_u8<const P: usize, const S: usize>(len_u8_ref: &u8) -> &[u8] {
     let len = match *len_u8_ref {
-        0 => large::read_actual_string_len::<P>(len_u8_ref),
+        0 => large::read_actual_string_len::<P, S>(len_u8_ref),
         l => l as usize,
     };
 
     let len_u8_ptr = len_u8_ref as *const u8;
     let start = unsafe { len_u8_ptr.add(1) };
-    let slice = unsafe { from_raw_parts(start, len) };
-    from_utf8(slice).unwrap()
+    unsafe { from_raw_parts(start, len) }
 }
 
-impl<const P: usize> Deref for PoolStr<P> {
+fn string_from_len_u8<const P: usize, const S: usize>(len_u8_ref: &u8) -> &str {
+    from_utf8(bytes_from_len_u8::<P, S>(len_u8_ref)).unwrap()
+}
+
+impl<const P: usize, const S: usize> Deref for PoolStr<P, S> {
     type Target = str;
     fn deref(&self) -> &Self::Target {
         match unsafe { self.len_ptr.as_ref() } {
-            Some(len_u8_ptr) => string_from_len_u8::<P>(len_u8_ptr),
+            Some(len_u8_ptr) => string_from_len_u8::<P, S>(len_u8_ptr),
             None => "",
         }
     }
 }
 
-fn deep_drop_pool<const P: usize>(pool_ptr: *const PoolInner<P>) {
+// None for the empty string
+fn pool_ptr_of<const P: usize, const S: usize>(len_ptr: *const u8) -> Option<*const PoolInner<P, S>> {
+    let len = unsafe { len_ptr.as_ref()? };
+
+    let pool_ptr = match *len {
+        0 => large::string_pool_ptr(len),
+        _ => small::string_pool_ptr(len),
+    };
+
+    Some(pool_ptr)
+}
+
+// called when a handle to the string at len_ptr goes away
+fn release<const P: usize, const S: usize>(len_ptr: *const u8) {
+    if let Some(pool_ptr) = pool_ptr_of::<P, S>(len_ptr) {
+        let pool = unsafe { pool_ptr.as_ref() }.unwrap();
+        if pool.dec_ref_count() {
+            deep_drop_pool(pool_ptr);
+        }
+    }
+}
+
+fn deep_drop_pool<const P: usize, const S: usize>(pool_ptr: *const PoolInner<P, S>) {
     let pool = unsafe { pool_ptr.as_ref() }.unwrap();
 
+    #[cfg(all(feature = "debug-drop", debug_assertions))]
+    pool.check(&mut |error| panic!("pool corruption detected at drop: {}", error));
+
     for pool_index in 0..P {
-        large::deep_drop(pool.first_large_string[pool_index].load(Relaxed));
-        small::deep_drop(pool.first_page[pool_index].load(Relaxed));
+        large::deep_drop(pool.first_large_string[pool_index].load(Relaxed), pool.allocator);
+        small::deep_drop(pool.first_page[pool_index].load(Relaxed), pool.allocator);
     }
 
-    let mut_ptr = (pool_ptr as usize) as *mut PoolInner<P>;
+    let mut_ptr = (pool_ptr as usize) as *mut PoolInner<P, S>;
     drop(unsafe { Box::from_raw(mut_ptr) });
 }
 
-impl<const P: usize> Drop for PoolStr<P> {
+impl<const P: usize, const S: usize> Drop for PoolStr<P, S> {
     fn drop(&mut self) {
-        if let Some(pool_ptr) = self.pool_ptr() {
-            let pool = unsafe { pool_ptr.as_ref() }.unwrap();
-            if pool.dec_ref_count() {
-                deep_drop_pool(pool_ptr);
-            }
-        }
+        release::<P, S>(self.len_ptr);
     }
 }
 
-impl<const P: usize> Drop for Pool<P> {
+impl<const P: usize, const S: usize> Drop for Pool<P, S> {
     fn drop(&mut self) {
         let pool = unsafe { self.inner.as_ref() }.unwrap();
         if pool.dec_ref_count() {
@@ -204,7 +843,7 @@ impl<const P: usize> Drop for Pool<P> {
     }
 }
 
-impl<const P: usize> Clone for PoolStr<P> {
+impl<const P: usize, const S: usize> Clone for PoolStr<P, S> {
     fn clone(&self) -> Self {
         if let Some(pool_ptr) = self.pool_ptr() {
             unsafe { pool_ptr.as_ref() }.unwrap().inc_ref_count();
@@ -217,7 +856,7 @@ impl<const P: usize> Clone for PoolStr<P> {
     }
 }
 
-impl<const P: usize> Clone for Pool<P> {
+impl<const P: usize, const S: usize> Clone for Pool<P, S> {
     fn clone(&self) -> Self {
         self.inner().inc_ref_count();
         Self {
@@ -227,14 +866,26 @@ impl<const P: usize> Clone for Pool<P> {
 }
 
 // Safe because of proper atomic operations
-unsafe impl<const P: usize> Send for PoolStr<P> {}
-unsafe impl<const P: usize> Sync for PoolStr<P> {}
-unsafe impl<const P: usize> Send for Pool<P> {}
-unsafe impl<const P: usize> Sync for Pool<P> {}
+unsafe impl<const P: usize, const S: usize> Send for PoolStr<P, S> {}
+unsafe impl<const P: usize, const S: usize> Sync for PoolStr<P, S> {}
+unsafe impl<const P: usize, const S: usize> Send for Pool<P, S> {}
+unsafe impl<const P: usize, const S: usize> Sync for Pool<P, S> {}
+
+// formats bytes as a string if they are valid UTF-8
+struct MaybeStr<'a>(&'a [u8]);
+
+impl<'a> core::fmt::Debug for MaybeStr<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match from_utf8(self.0) {
+            Ok(string) => string.fmt(f),
+            Err(_) => self.0.fmt(f),
+        }
+    }
+}
 
-struct PoolPages<'a, const P: usize>(&'a PoolInner<P>);
+struct PoolPages<'a, const P: usize, const S: usize>(&'a PoolInner<P, S>);
 
-impl<'a, const P: usize> core::fmt::Debug for PoolPages<'a, P> {
+impl<'a, const P: usize, const S: usize> core::fmt::Debug for PoolPages<'a, P, S> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut output = f.debug_list();
         self.0.debug_pages(&mut output);
@@ -242,9 +893,9 @@ impl<'a, const P: usize> core::fmt::Debug for PoolPages<'a, P> {
     }
 }
 
-struct PoolLargeStrings<'a, const P: usize>(&'a PoolInner<P>);
+struct PoolLargeStrings<'a, const P: usize, const S: usize>(&'a PoolInner<P, S>);
 
-impl<'a, const P: usize> core::fmt::Debug for PoolLargeStrings<'a, P> {
+impl<'a, const P: usize, const S: usize> core::fmt::Debug for PoolLargeStrings<'a, P, S> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut output = f.debug_list();
         self.0.debug_large_strings(&mut output);
@@ -252,7 +903,7 @@ impl<'a, const P: usize> core::fmt::Debug for PoolLargeStrings<'a, P> {
     }
 }
 
-impl<const P: usize> core::fmt::Debug for Pool<P> {
+impl<const P: usize, const S: usize> core::fmt::Debug for Pool<P, S> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let inner = self.inner();
         let mut output = f.debug_struct("Pool");
@@ -263,6 +914,10 @@ impl<const P: usize> core::fmt::Debug for Pool<P> {
     }
 }
 
+/// Shortest string made only of zeros that is stored as a large string.
+#[cfg(test)]
+pub(crate) const LARGE_STRING: &str = "0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
+
 #[test]
 fn edge_case_1() {
     let small_string_1 = "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001";
@@ -344,3 +999,666 @@ fn various_tests() {
 
     std::println!("AFTER VARIOUS TESTS: {:#?}", pool);
 }
+
+#[test]
+fn raw_slot() {
+    let pool: Pool<1> = Pool::new();
+    let small = pool.intern("Hello world!");
+    let slot = small.raw_slot().unwrap();
+
+    // decode the slot back into a string
+    let len = slot[0] as usize;
+    assert_eq!(len, slot.len() - 1);
+    assert_eq!(from_utf8(&slot[1..]).unwrap(), "Hello world!");
+
+    assert_eq!(pool.intern(LARGE_STRING).raw_slot(), None);
+    assert_eq!(PoolStr::<1>::empty().raw_slot(), None);
+}
+
+#[test]
+fn audit_duplicates() {
+    let pool: Pool<4> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.intern("krjgegyhergyeurgyeyrg");
+    pool.intern("gjnberguieriu");
+    assert_eq!(pool.audit_duplicates(), 0);
+
+    pool.inner().push_small_duplicate("krjgegyhergyeurgyeyrg");
+    assert_eq!(pool.audit_duplicates(), 1);
+}
+
+#[test]
+fn len() {
+    let pool: Pool<4> = Pool::new();
+    assert!(pool.is_empty());
+    assert_eq!(pool.len(), 0);
+
+    pool.intern("");
+    assert!(pool.is_empty());
+
+    pool.intern("gjnberguieriu");
+    pool.intern("gjnberguieriu");
+    pool.intern(LARGE_STRING);
+    assert!(!pool.is_empty());
+    assert_eq!(pool.len(), 2);
+
+    // counting doesn't create handles
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1);
+}
+
+#[test]
+fn contains() {
+    let pool: Pool<4> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.intern(LARGE_STRING);
+
+    assert!(pool.contains(""));
+    assert!(pool.contains("gjnberguieriu"));
+    assert!(pool.contains(LARGE_STRING));
+    assert!(!pool.contains("yikes"));
+    assert!(!pool.contains(&LARGE_STRING[1..]));
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1);
+}
+
+#[test]
+fn iter() {
+    let strings = ["gjnberguieriu", "yikes", "ryjtyjty", LARGE_STRING];
+
+    let pool: Pool<4> = Pool::new();
+    for string in strings {
+        pool.intern(string);
+        pool.intern(string);
+    }
+
+    let mut visited: alloc::vec::Vec<_> = pool.iter().collect();
+    visited.sort_unstable();
+    let mut expected = strings;
+    expected.sort_unstable();
+    assert_eq!(visited, expected);
+
+    let handles: alloc::vec::Vec<_> = pool.iter_pool_str().collect();
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1 + strings.len());
+    assert!(handles.iter().all(|pool_str| pool.find(pool_str) == Some(pool_str.clone())));
+}
+
+#[test]
+fn total_string_bytes() {
+    let pool: Pool<4> = Pool::new();
+    assert_eq!((pool.total_string_bytes(), pool.average_length()), (0, 0.0));
+
+    for string in ["abc", "abcde", "abc", "", LARGE_STRING] {
+        pool.intern(string);
+    }
+
+    assert_eq!(pool.total_string_bytes(), 8 + LARGE_STRING.len());
+    assert_eq!(pool.average_length(), (8 + LARGE_STRING.len()) as f64 / 3.0);
+}
+
+#[test]
+fn page_sizes() {
+    use alloc::{format, vec::Vec};
+
+    fn check<const S: usize>() {
+        let pool: Pool<2, S> = Pool::new();
+        let strings: Vec<_> = (0..300).map(|i| format!("string number {}", i)).collect();
+
+        for string in &strings {
+            pool.intern(string);
+        }
+
+        for string in &strings {
+            let pool_str = pool.find(string).unwrap();
+            assert_eq!(&*pool_str, string);
+            assert_eq!(pool_str.pool().unwrap().find(string), Some(pool_str.clone()));
+        }
+
+        assert_eq!(pool.len(), strings.len());
+        assert_eq!(pool.memory_usage(), size_of::<PoolInner<2, S>>() + pool.inner().page_count() * S);
+        assert_eq!(pool.verify(), Ok(()));
+    }
+
+    check::<256>();
+    check::<1024>();
+    check::<4096>();
+}
+
+#[test]
+fn memory_usage() {
+    use alloc::{format, vec::Vec};
+
+    let pool: Pool<4> = Pool::new();
+    let empty = size_of::<PoolInner<4, 1024>>();
+    assert_eq!(pool.memory_usage(), empty);
+
+    pool.intern("yikes");
+    assert_eq!(pool.memory_usage(), empty + 1024);
+
+    let large = large::large_string_layout::<4, 1024>(LARGE_STRING.len()).size();
+    pool.intern(LARGE_STRING);
+    pool.intern(LARGE_STRING);
+    assert_eq!(pool.memory_usage(), empty + 1024 + large);
+
+    // copies discarded after losing a race aren't counted
+    let pool: Pool<4> = Pool::new();
+    let strings: Vec<_> = (0..200).map(|i| format!("{:0>130}", i)).collect();
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| strings.iter().for_each(|string| _ = pool.intern(string)));
+        }
+    });
+
+    assert_eq!(pool.inner().large_string_count(), strings.len());
+    let large = large::large_string_layout::<4, 1024>(130).size();
+    assert_eq!(pool.memory_usage(), empty + strings.len() * large);
+}
+
+#[test]
+fn intern_observing() {
+    let pool: Pool<4> = Pool::new();
+    let mut new_strings = alloc::vec::Vec::new();
+
+    for _ in 0..3 {
+        for string in ["gjnberguieriu", LARGE_STRING, "", "ryjtyjty"] {
+            pool.intern_observing(string, |s| new_strings.push(s.clone()));
+        }
+    }
+
+    assert_eq!(new_strings, ["gjnberguieriu", LARGE_STRING, "ryjtyjty"]);
+}
+
+#[test]
+fn inner_ptr() {
+    let pool: Pool<4> = Pool::new();
+    let pool_str = pool.intern("gjnberguieriu");
+    let ptr = pool.clone().into_inner_ptr();
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 3);
+
+    let pool_2 = unsafe { Pool::<4>::from_inner_ptr(ptr) };
+    assert_eq!(pool_2.find("gjnberguieriu"), Some(pool_str));
+    drop(pool_2);
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 1);
+}
+
+#[test]
+fn bytes_eq() {
+    let strings = ["gjnberguieriu", "rjuebuinh99€€", "", "rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€"];
+
+    let pool_1: Pool<4> = Pool::new();
+    let pool_2: Pool<4> = Pool::new();
+
+    for a in strings {
+        for b in strings {
+            let (a_1, b_2) = (pool_1.intern(a), pool_2.intern(b));
+            assert_eq!(a_1.bytes_eq(&b_2), a_1.deref() == b_2.deref());
+            assert_eq!(a_1 == b_2, a == b);
+        }
+    }
+}
+
+#[test]
+fn deep_clone() {
+    let pool: Pool<4> = Pool::new();
+    let small = pool.intern("gjnberguieriu");
+    let large = pool.intern(LARGE_STRING);
+
+    let copy = pool.deep_clone();
+    assert_eq!(copy.len(), 2);
+    assert_eq!(copy.inner().ref_count.load(Relaxed), 1);
+
+    let small_copy = copy.find("gjnberguieriu").unwrap();
+    assert_ne!(small_copy.raw_slot().unwrap().as_ptr(), small.raw_slot().unwrap().as_ptr());
+
+    drop((pool, small, large));
+    assert_eq!(&*small_copy, "gjnberguieriu");
+    assert_eq!(&*copy.find(LARGE_STRING).unwrap(), LARGE_STRING);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn write_to() {
+    let pool: Pool<4> = Pool::new();
+    let mut output = std::vec::Vec::new();
+
+    for string in ["Hello", "", " ", "world€!"] {
+        pool.intern(string).write_to(&mut output).unwrap();
+    }
+
+    assert_eq!(output, "Hello world€!".as_bytes());
+}
+
+#[test]
+fn concurrent_interning() {
+    use alloc::{format, vec::Vec};
+
+    let pool: Pool<1> = Pool::new();
+    let strings: Vec<_> = (0..500).map(|i| format!("string number {}", i)).collect();
+
+    std::thread::scope(|scope| {
+        for offset in 0..8 {
+            let (pool, strings) = (&pool, &strings);
+            scope.spawn(move || {
+                for i in 0..strings.len() {
+                    let string = &strings[(i + offset * 61) % strings.len()];
+                    assert_eq!(&*pool.intern(string), string);
+                }
+            });
+        }
+    });
+
+    for string in &strings {
+        assert_eq!(&*pool.find(string).unwrap(), string);
+    }
+
+    assert_eq!(pool.len() - pool.audit_duplicates(), strings.len());
+}
+
+#[test]
+fn concurrent_intern_and_find() {
+    use alloc::{format, vec::Vec};
+
+    let pool: Pool<1> = Pool::new();
+    let strings: Vec<_> = (0..2000).map(|i| format!("string number {}", i).repeat(1 + i % 8)).collect();
+
+    std::thread::scope(|scope| {
+        for offset in 0..2 {
+            let (pool, strings) = (&pool, &strings);
+            scope.spawn(move || {
+                for string in strings.iter().skip(offset).step_by(2) {
+                    pool.intern(string);
+                }
+            });
+        }
+
+        for _ in 0..4 {
+            let (pool, strings) = (&pool, &strings);
+            scope.spawn(move || {
+                for string in strings.iter().cycle().take(strings.len() * 4) {
+                    if let Some(found) = pool.find(string) {
+                        assert_eq!(&*found, string);
+                    }
+                }
+            });
+        }
+    });
+
+    assert_eq!(pool.len() - pool.audit_duplicates(), strings.len());
+}
+
+#[test]
+fn is_ascii_and_char_count() {
+    let large_string_3 = "rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€rjuebuinh99€€";
+
+    let pool: Pool<4> = Pool::new();
+
+    for string in ["", "gjnberguieriu", "rjuebuinh99€€", LARGE_STRING, large_string_3] {
+        let pool_str = pool.intern(string);
+        assert_eq!(pool_str.is_ascii(), string.is_ascii());
+        assert_eq!(pool_str.char_count(), string.chars().count());
+    }
+}
+
+#[test]
+fn byte_at_and_char_at() {
+    let pool: Pool<4> = Pool::new();
+    let pool_str = pool.intern("a€b");
+
+    assert_eq!(pool_str.byte_at(0), Some(b'a'));
+    assert_eq!(pool_str.byte_at(1), Some(0xe2));
+    assert_eq!(pool_str.byte_at(4), Some(b'b'));
+    assert_eq!(pool_str.byte_at(5), None);
+
+    assert_eq!(pool_str.char_at(0), Some('a'));
+    assert_eq!(pool_str.char_at(1), Some('€'));
+    assert_eq!(pool_str.char_at(2), Some('b'));
+    assert_eq!(pool_str.char_at(3), None);
+
+    assert_eq!(PoolStr::<4>::empty().byte_at(0), None);
+    assert_eq!(PoolStr::<4>::empty().char_at(0), None);
+}
+
+#[test]
+fn copy_to_array() {
+    let pool: Pool<4> = Pool::new();
+    let key = pool.intern("key");
+
+    assert_eq!(key.copy_to_array::<8>(), Some((*b"key\0\0\0\0\0", 3)));
+    assert_eq!(key.copy_to_array::<3>(), Some((*b"key", 3)));
+    assert_eq!(key.copy_to_array::<2>(), None);
+    assert_eq!(PoolStr::<4>::empty().copy_to_array::<0>(), Some(([], 0)));
+}
+
+#[test]
+fn len_and_as_bytes() {
+    let pool: Pool<4> = Pool::new();
+
+    for string in ["", "gjnberguieriu", "rjuebuinh99€€", LARGE_STRING] {
+        let pool_str = pool.intern(string);
+        assert_eq!(pool_str.len(), string.len());
+        assert_eq!(pool_str.is_empty(), string.is_empty());
+        assert_eq!(pool_str.as_bytes(), string.as_bytes());
+    }
+}
+
+#[test]
+fn pool() {
+    let pool: Pool<4> = Pool::new();
+    let name = pool.intern("main");
+
+    let same_pool = name.pool().unwrap();
+    assert_eq!(pool.inner().ref_count.load(Relaxed), 3);
+    assert_eq!(same_pool.intern("main.rs"), pool.find("main.rs").unwrap());
+
+    drop((pool, same_pool));
+    assert_eq!(name.pool().unwrap().find("main"), Some(name));
+    assert!(PoolStr::<4>::empty().pool().is_none());
+}
+
+#[test]
+fn as_str() {
+    let pool: Pool<4> = Pool::new();
+    let other: Pool<4> = Pool::new();
+
+    let pool_str = pool.intern("gjnberguieriu");
+    assert_eq!(pool_str.as_str(), "gjnberguieriu");
+
+    let cached = pool.as_str(&pool_str).unwrap();
+    drop(pool_str);
+    assert_eq!(cached, "gjnberguieriu");
+
+    assert_eq!(pool.as_str(&PoolStr::empty()), Some(""));
+    assert_eq!(pool.as_str(&other.intern("yikes")), None);
+}
+
+#[test]
+fn as_char() {
+    let pool: Pool<4> = Pool::new();
+
+    assert_eq!(pool.intern("a").as_char(), Some('a'));
+    assert_eq!(pool.intern("€").as_char(), Some('€'));
+    assert_eq!(pool.intern("\u{1D11E}").as_char(), Some('\u{1D11E}'));
+    assert_eq!(pool.intern("ab").as_char(), None);
+    assert_eq!(pool.intern("€€").as_char(), None);
+    assert_eq!(pool.intern("").as_char(), None);
+}
+
+#[test]
+fn intern_adjacent() {
+    let page_of = |pool_str: &PoolStr<1>| (pool_str.raw_slot().unwrap().as_ptr() as usize) / 1024;
+
+    let pool: Pool<1> = Pool::new();
+    pool.intern("gjnberguieriu");
+
+    let (key, value) = pool.intern_adjacent("key", "value");
+    assert_eq!((&*key, &*value), ("key", "value"));
+    assert_eq!(page_of(&key), page_of(&value));
+
+    let key_slot = key.raw_slot().unwrap();
+    assert_eq!(value.raw_slot().unwrap().as_ptr(), key_slot.as_ptr_range().end);
+
+    // existing strings are reused
+    let (key_2, other) = pool.intern_adjacent("key", "other");
+    assert_eq!(key_2.raw_slot().unwrap().as_ptr(), key_slot.as_ptr());
+    assert_eq!(&*other, "other");
+
+    // not enough space in the first page
+    let filler = &LARGE_STRING[..121];
+    for i in 0..7 {
+        pool.intern(&alloc::format!("{}{}", filler, i));
+    }
+
+    let (a, b) = pool.intern_adjacent(&filler[..100], &filler[..50]);
+    assert_eq!(page_of(&a), page_of(&b));
+    assert_eq!(pool.find(&filler[..100]), Some(a));
+    assert_eq!(pool.find(&filler[..50]), Some(b));
+}
+
+#[test]
+fn byte_comparisons() {
+    let pool: Pool<1> = Pool::new();
+    let ascii = pool.intern("apple");
+    let multibyte = pool.intern("€uro");
+
+    let (apple, apples, euro): (&[u8], &[u8], &[u8]) = (b"apple", b"apples", "€uro".as_bytes());
+
+    assert!(ascii == *apple);
+    assert!(ascii == apple);
+    assert!(ascii != apples);
+    assert!(multibyte == euro);
+    assert!(multibyte != euro[1..]);
+    assert!(PoolStr::<1>::empty() == b""[..]);
+}
+
+#[test]
+fn tier_boundaries() {
+    use alloc::{string::String, vec::Vec};
+
+    fn check<const P: usize>(seed: u64) {
+        let mut state = seed;
+        let mut random = move || {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+
+        let strings: Vec<String> = (0..256)
+            .flat_map(|len| ['a', 'é'].map(|c| (0..len).map(|i| match i % 7 {
+                0 => c,
+                _ => char::from(b'0' + (i % 10) as u8),
+            }).collect::<String>()))
+            .filter(|string| string.len() < 256)
+            .collect();
+
+        let mut unique: Vec<&str> = strings.iter().map(|s| s.as_str()).filter(|s| !s.is_empty()).collect();
+        unique.sort_unstable();
+        unique.dedup();
+
+        let pool: Pool<P> = Pool::new();
+
+        for _ in 0..3 {
+            let mut order: Vec<&String> = strings.iter().collect();
+            for i in (1..order.len()).rev() {
+                order.swap(i, random() % (i + 1));
+            }
+
+            for string in order {
+                assert_eq!(&*pool.intern(string), string);
+            }
+
+            for string in &strings {
+                assert_eq!(&*pool.find(string).unwrap(), string);
+            }
+
+            assert_eq!(pool.len(), unique.len());
+        }
+    }
+
+    check::<1>(1);
+    check::<2>(2);
+    check::<4>(3);
+    check::<16>(4);
+}
+
+#[test]
+fn hash_into() {
+    use core::hash::{Hasher, BuildHasher};
+    use std::collections::hash_map::RandomState;
+
+    let build_hasher = RandomState::new();
+
+    let composite_hash = |pool: &Pool<4>| {
+        let mut state = build_hasher.build_hasher();
+        for string in ["gjnberguieriu", LARGE_STRING, ""] {
+            pool.intern(string).hash_into(&mut state);
+        }
+        state.finish()
+    };
+
+    let pool_1: Pool<4> = PoolConfig::new().seed(7).build();
+    let pool_2: Pool<4> = PoolConfig::new().seed(7).build();
+    assert_eq!(composite_hash(&pool_1), composite_hash(&pool_1));
+    assert_eq!(composite_hash(&pool_1), composite_hash(&pool_2));
+
+    let large = pool_1.intern(LARGE_STRING);
+    let mut state = build_hasher.build_hasher();
+    large.hash_into(&mut state);
+    let mut expected = build_hasher.build_hasher();
+    expected.write_u64(pool_1.inner().hash(LARGE_STRING.as_bytes()));
+    assert_eq!(state.finish(), expected.finish());
+}
+
+#[test]
+fn find_never_allocates() {
+    use alloc::{format, vec::Vec};
+
+    let pool: Pool<4> = Pool::new();
+    let strings: Vec<_> = (0..2000).map(|i| format!("string number {}", i)).collect();
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for string in &strings {
+                pool.intern(string);
+            }
+        });
+
+        let before = test_alloc::allocations();
+        let mut found = 0;
+
+        while found < strings.len() {
+            found = strings.iter().filter(|s| pool.find(s).is_some()).count();
+        }
+
+        assert_eq!(test_alloc::allocations(), before);
+    });
+
+    assert!(strings.iter().all(|s| pool.find(s).is_some()));
+}
+
+#[test]
+fn sort_key_bytes() {
+    use alloc::vec::Vec;
+
+    let pool: Pool<4> = Pool::new();
+    let strings = ["zebra", "Zebra", "é", "e", "", "\u{10000}", "\u{ffff}", "ab", "a"];
+    let mut by_key: Vec<_> = strings.iter().map(|s| pool.intern(s)).collect();
+    let mut by_cmp = by_key.clone();
+
+    by_key.sort_by(|a, b| a.sort_key_bytes().cmp(b.sort_key_bytes()));
+    by_cmp.sort_by(|a, b| str::cmp(a, b));
+    assert_eq!(by_key, by_cmp);
+}
+
+#[test]
+fn verify() {
+    let pool: Pool<1> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.intern(LARGE_STRING);
+    assert_eq!(pool.verify(), Ok(()));
+
+    pool.inner().corrupt_first_large_string(0);
+    let error = pool.verify().unwrap_err();
+    assert_eq!((error.subpool, error.offset), (0, 0));
+    assert_eq!(error.description, "large string header points to another pool");
+
+    // strings of a corrupted pool can't be freed
+    core::mem::forget(pool);
+
+    let pool: Pool<1> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.inner().corrupt_first_page(0);
+    let error = pool.verify().unwrap_err();
+    assert_eq!((error.subpool, error.offset), (0, 0));
+    assert_eq!(error.description, "page header points to another pool");
+    core::mem::forget(pool);
+}
+
+#[test]
+#[cfg(all(feature = "debug-drop", debug_assertions))]
+#[should_panic(expected = "pool corruption detected at drop")]
+fn debug_drop() {
+    let pool: Pool<1> = Pool::new();
+    pool.intern("gjnberguieriu");
+    pool.inner().corrupt_first_page(0);
+}
+
+#[test]
+fn try_intern() {
+    let pool: Pool<1> = Pool::new();
+
+    // no allocation may happen until the flag is cleared
+    test_alloc::set_failing(true);
+    let small = pool.try_intern("small");
+    let large = pool.try_intern(LARGE_STRING);
+    let empty = pool.try_intern("");
+    test_alloc::set_failing(false);
+
+    assert_eq!(small, Err(InternError::Alloc));
+    assert_eq!(large, Err(InternError::Alloc));
+    assert_eq!(empty.as_deref(), Ok(""));
+    assert!(pool.is_empty());
+    assert_eq!(pool.verify(), Ok(()));
+
+    let small = pool.try_intern("small").unwrap();
+
+    // the first page has room for this one
+    test_alloc::set_failing(true);
+    let other = pool.try_intern("other");
+    let again = pool.try_intern("small");
+    test_alloc::set_failing(false);
+
+    assert_eq!(other.as_deref(), Ok("other"));
+    assert_eq!(again, Ok(small));
+    assert_eq!(pool.try_intern(LARGE_STRING).as_deref(), Ok(LARGE_STRING));
+    assert_eq!(pool.len(), 3);
+}
+
+#[test]
+fn extract_subpool() {
+    let pool: Pool<4> = Pool::new();
+    let strings = ["gjnberguieriu", "krjgegyhergyeurgyeyrg", "ryjtyjty", "yikes", "a", "b", LARGE_STRING];
+
+    for string in strings {
+        pool.intern(string);
+    }
+
+    let mut extracted_len = 0;
+
+    for index in 0..4 {
+        let extracted = pool.extract_subpool(index);
+        let inner = pool.inner();
+
+        for string in strings {
+            let in_subpool = inner.index_for(string.as_bytes()) == index;
+            assert_eq!(extracted.contains(string), in_subpool);
+        }
+
+        extracted_len += extracted.len();
+    }
+
+    assert_eq!(extracted_len, strings.len());
+
+    // the extracted pool outlives the source
+    let extracted = pool.extract_subpool(pool.inner().index_for(b"yikes"));
+    drop(pool);
+    assert_eq!(&*extracted.find("yikes").unwrap(), "yikes");
+}
+
+#[test]
+fn as_cow() {
+    use alloc::borrow::Cow;
+
+    fn is_borrowed<'a, C: Into<Cow<'a, str>>>(string: C, expected: &str) -> bool {
+        let cow = string.into();
+        assert_eq!(cow, expected);
+        matches!(cow, Cow::Borrowed(_))
+    }
+
+    let pool: Pool<4> = Pool::new();
+    let pool_str = pool.intern("yikes");
+
+    assert!(is_borrowed(pool_str.as_cow(), "yikes"));
+    assert!(core::ptr::eq(pool_str.as_cow().as_ptr(), pool_str.as_ptr()));
+    assert!(is_borrowed(PoolStr::<4>::empty().as_cow(), ""));
+}
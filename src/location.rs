@@ -0,0 +1,74 @@
+use super::{PoolStr, small, large};
+
+/// Where a [`PoolStr`] is stored, see [`PoolStr::debug_location`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugLocation {
+    /// The empty string, which isn't stored
+    Empty,
+    /// A slot in a page of small strings
+    Small {
+        /// Index of the subpool
+        subpool: usize,
+        /// Address of the page
+        page: *const (),
+        /// Offset of the slot's length byte, after the page header
+        offset: usize,
+    },
+    /// A large string
+    Large {
+        /// Index of the subpool
+        subpool: usize,
+        /// Address of the large string header
+        header: *const (),
+    },
+}
+
+impl<const P: usize, const S: usize> PoolStr<P, S> {
+    /// Describes where this string is stored
+    ///
+    /// This is meant to debug the crate itself.
+    pub fn debug_location(&self) -> DebugLocation {
+        let (Some(pool), Some(len_u8_ref)) = (self.pool_inner(), unsafe { self.len_ptr.as_ref() }) else {
+            return DebugLocation::Empty;
+        };
+
+        let subpool = pool.index_for(self.as_bytes());
+
+        match *len_u8_ref {
+            0 => DebugLocation::Large {
+                subpool,
+                header: large::header_ptr::<P, S>(len_u8_ref),
+            },
+            _ => {
+                let (page, offset) = small::slot_location::<P, S>(len_u8_ref);
+                DebugLocation::Small { subpool, page, offset }
+            },
+        }
+    }
+}
+
+#[test]
+fn debug_location() {
+    use super::{Pool, LARGE_STRING};
+
+    let pool: Pool<1> = Pool::new();
+    let (a, bc) = (pool.intern("a"), pool.intern("bc"));
+
+    let DebugLocation::Small { subpool: 0, page, offset: 0 } = a.debug_location() else {
+        panic!("unexpected location of a: {:?}", a.debug_location());
+    };
+
+    // after the length byte and the byte of "a"
+    assert_eq!(bc.debug_location(), DebugLocation::Small { subpool: 0, page, offset: 2 });
+
+    let large = pool.intern(LARGE_STRING);
+    let header = pool.inner().first_large_string[0].load(core::sync::atomic::Ordering::Relaxed);
+    assert_eq!(large.debug_location(), DebugLocation::Large { subpool: 0, header: header as *const () });
+
+    let pool: Pool<4> = Pool::new();
+    let yikes = pool.intern("yikes");
+    let subpool = pool.inner().index_for(b"yikes");
+    assert!(matches!(yikes.debug_location(), DebugLocation::Small { subpool: s, .. } if s == subpool));
+
+    assert_eq!(PoolStr::<4>::empty().debug_location(), DebugLocation::Empty);
+}
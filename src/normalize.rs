@@ -0,0 +1,404 @@
+use super::{Pool, PoolStr};
+use alloc::{string::{String, FromUtf16Error, ToString}, borrow::Cow};
+use core::num::ParseFloatError;
+
+/// Error returned by [`Pool::intern_ascii`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonAsciiError {
+    /// Index of the first non-ASCII byte
+    pub position: usize,
+}
+
+impl core::fmt::Display for NonAsciiError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "non-ASCII byte at index {}", self.position)
+    }
+}
+
+/// Error returned by [`Pool::intern_number`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseNumberError {
+    /// The string isn't a number
+    Invalid(ParseFloatError),
+    /// The number is infinite or NaN, or overflows an [`f64`]
+    NotFinite,
+}
+
+impl core::fmt::Display for ParseNumberError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Invalid(error) => error.fmt(f),
+            Self::NotFinite => write!(f, "number is not finite"),
+        }
+    }
+}
+
+/// Escaping applied by [`Pool::intern_escaped`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escaper {
+    /// SQL identifier: quoted in `"`, with inner `"` doubled,
+    /// unless it's made of ASCII letters, digits and `_` and
+    /// doesn't start with a digit
+    SqlIdentifier,
+    /// POSIX shell word: quoted in `'`, with inner `'` written
+    /// as `'\''`, unless it's made of ASCII letters, digits
+    /// and `_@%+=:,./-`
+    Shell,
+}
+
+impl Escaper {
+    fn is_plain(self, string: &str) -> bool {
+        let mut bytes = string.bytes();
+
+        match self {
+            Self::SqlIdentifier => {
+                let first = bytes.next().is_some_and(|b| b.is_ascii_alphabetic() || b == b'_');
+                first && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+            },
+            Self::Shell => {
+                let safe = |b: u8| b.is_ascii_alphanumeric() || b"_@%+=:,./-".contains(&b);
+                !string.is_empty() && bytes.all(safe)
+            },
+        }
+    }
+
+    fn escape(self, string: &str) -> String {
+        let (quote, escaped_quote) = match self {
+            Self::SqlIdentifier => ('"', "\"\""),
+            Self::Shell => ('\'', "'\\''"),
+        };
+
+        let mut escaped = String::with_capacity(string.len() + 2);
+        escaped.push(quote);
+
+        for c in string.chars() {
+            match c == quote {
+                true => escaped.push_str(escaped_quote),
+                false => escaped.push(c),
+            }
+        }
+
+        escaped.push(quote);
+        escaped
+    }
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Interns `string` escaped with `escaper`
+    ///
+    /// No allocation is made if `string` doesn't need escaping.
+    pub fn intern_escaped(&self, string: &str, escaper: Escaper) -> PoolStr<P, S> {
+        match escaper.is_plain(string) {
+            true => self.intern(string),
+            false => self.intern(&escaper.escape(string)),
+        }
+    }
+
+    /// Interns the canonical spelling of the number in `string`
+    ///
+    /// `string` is parsed as an [`f64`] and formatted back, so `1.0`,
+    /// `1.00` and `+1` are all interned as `1`. Spellings which parse
+    /// to the same [`f64`] collapse, even if they differ beyond its
+    /// precision. Negative zero is interned as `0`.
+    ///
+    /// Infinities and NaN, including numbers too large for an [`f64`],
+    /// are rejected.
+    pub fn intern_number(&self, string: &str) -> Result<PoolStr<P, S>, ParseNumberError> {
+        let number: f64 = string.parse().map_err(ParseNumberError::Invalid)?;
+
+        match number {
+            _ if !number.is_finite() => Err(ParseNumberError::NotFinite),
+            0.0 => Ok(self.intern("0")),
+            _ => Ok(self.intern(&number.to_string())),
+        }
+    }
+
+    /// Interns `string` with every `from` replaced by `to`
+    ///
+    /// No allocation is made if `from` isn't found in `string`.
+    pub fn intern_replacing(&self, string: &str, from: &str, to: &str) -> PoolStr<P, S> {
+        match string.contains(from) {
+            true => self.intern(&string.replace(from, to)),
+            false => self.intern(string),
+        }
+    }
+
+    /// Interns `string` in ASCII lowercase, returning its hash as well
+    ///
+    /// The hash is the one used internally by the pool; unless
+    /// set in [`PoolConfig`](crate::PoolConfig), its seed is
+    /// chosen randomly when this crate is built.
+    pub fn intern_ci_hashed(&self, string: &str) -> (PoolStr<P, S>, u64) {
+        let lowercase;
+        let string = match string.bytes().any(|b| b.is_ascii_uppercase()) {
+            true => {
+                lowercase = string.to_ascii_lowercase();
+                &lowercase
+            },
+            false => string,
+        };
+
+        // hashed once, for both the pool and the caller
+        let inner = self.inner();
+        let hash = inner.hash(string.as_bytes());
+        (inner.intern_hashed(string, hash), hash)
+    }
+
+    /// Decodes UTF-16 `units` and interns the result
+    pub fn intern_utf16(&self, units: &[u16]) -> Result<PoolStr<P, S>, FromUtf16Error> {
+        Ok(self.intern(&String::from_utf16(units)?))
+    }
+
+    /// Decodes UTF-16 `units` and interns the result
+    ///
+    /// Invalid data is replaced with `U+FFFD`.
+    pub fn intern_utf16_lossy(&self, units: &[u16]) -> PoolStr<P, S> {
+        self.intern(&String::from_utf16_lossy(units))
+    }
+
+    /// Interns ASCII `bytes`, skipping general UTF-8 validation
+    pub fn intern_ascii(&self, bytes: &[u8]) -> Result<PoolStr<P, S>, NonAsciiError> {
+        if !bytes.is_ascii() {
+            let position = bytes.iter().position(|b| !b.is_ascii()).unwrap();
+            return Err(NonAsciiError { position });
+        }
+
+        // ASCII is valid UTF-8
+        Ok(self.intern(unsafe { core::str::from_utf8_unchecked(bytes) }))
+    }
+
+    /// Interns the output of `redactor` for `string`
+    ///
+    /// When `redactor` returns [`Cow::Borrowed`], that slice is interned as is.
+    pub fn intern_redacted<F: Fn(&str) -> Cow<str>>(&self, string: &str, redactor: F) -> PoolStr<P, S> {
+        self.intern(&redactor(string))
+    }
+
+    /// Interns `string` with `\r\n` and lone `\r` converted to `\n`
+    ///
+    /// No allocation is made if `string` contains no `\r`.
+    pub fn intern_normalize_newlines(&self, string: &str) -> PoolStr<P, S> {
+        if !string.contains('\r') {
+            return self.intern(string);
+        }
+
+        let mut normalized = String::with_capacity(string.len());
+        let mut chars = string.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\r' => {
+                    chars.next_if_eq(&'\n');
+                    normalized.push('\n');
+                },
+                c => normalized.push(c),
+            }
+        }
+
+        self.intern(&normalized)
+    }
+
+    /// Interns `string` trimmed and in ASCII lowercase
+    ///
+    /// No allocation is made if `string` has no ASCII uppercase letter.
+    pub fn intern_trimmed_lowercase(&self, string: &str) -> PoolStr<P, S> {
+        let trimmed = string.trim();
+
+        match trimmed.bytes().any(|b| b.is_ascii_uppercase()) {
+            true => self.intern(&trimmed.to_ascii_lowercase()),
+            false => self.intern(trimmed),
+        }
+    }
+}
+
+#[test]
+fn intern_replacing() {
+    let pool: Pool<1> = Pool::new();
+    let original = pool.intern("no_slash");
+
+    let no_match = pool.intern_replacing("no_slash", "/", "_");
+    assert_eq!(no_match.raw_slot().unwrap().as_ptr(), original.raw_slot().unwrap().as_ptr());
+
+    assert_eq!(&*pool.intern_replacing("a/b", "/", "_"), "a_b");
+    assert_eq!(&*pool.intern_replacing("a/b/c/d", "/", "_"), "a_b_c_d");
+    assert_eq!(pool.intern_replacing("a/b/c/d", "/", "_"), pool.find("a_b_c_d").unwrap());
+}
+
+#[test]
+fn intern_ci_hashed() {
+    use core::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    use crate::{PoolConfig, LARGE_STRING, PoolHasher, hash::{CityHash, SEED_U64}};
+
+    let pool: Pool<1> = Pool::new();
+    let (a, hash_a) = pool.intern_ci_hashed("Content-Type");
+    let (b, hash_b) = pool.intern_ci_hashed("content-type");
+    let (c, hash_c) = pool.intern_ci_hashed("CONTENT-TYPE");
+
+    assert_eq!(&*a, "content-type");
+    assert_eq!(a, b);
+    assert_eq!(b, c);
+    assert_eq!(hash_a, hash_b);
+    assert_eq!(hash_b, hash_c);
+
+    // new strings are hashed once
+    struct Counting(AtomicUsize);
+
+    impl PoolHasher for Counting {
+        fn hash(&self, bytes: &[u8], seed: u64) -> u64 {
+            self.0.fetch_add(1, SeqCst);
+            CityHash.hash(bytes, seed)
+        }
+    }
+
+    static COUNTING: Counting = Counting(AtomicUsize::new(0));
+    let pool: Pool<4> = PoolConfig::new().hasher(&COUNTING).build();
+
+    for string in ["Content-Type", LARGE_STRING] {
+        let before = COUNTING.0.load(SeqCst);
+        let (pool_str, hash) = pool.intern_ci_hashed(string);
+        assert_eq!(COUNTING.0.load(SeqCst), before + 1);
+        assert_eq!(pool.find(&string.to_ascii_lowercase()), Some(pool_str));
+        assert_eq!(hash, CityHash.hash(string.to_ascii_lowercase().as_bytes(), SEED_U64));
+    }
+}
+
+#[test]
+fn intern_utf16() {
+    use alloc::vec::Vec;
+
+    let pool: Pool<1> = Pool::new();
+
+    let bmp: Vec<u16> = "héllo €".encode_utf16().collect();
+    assert_eq!(&*pool.intern_utf16(&bmp).unwrap(), "héllo €");
+
+    // U+1D11E is encoded as a surrogate pair
+    let supplementary = [0x0061, 0xD834, 0xDD1E];
+    assert_eq!(&*pool.intern_utf16(&supplementary).unwrap(), "a\u{1D11E}");
+
+    let unpaired = [0x0061, 0xD834, 0x0062];
+    assert!(pool.intern_utf16(&unpaired).is_err());
+    assert_eq!(&*pool.intern_utf16_lossy(&unpaired), "a\u{FFFD}b");
+}
+
+#[test]
+fn intern_ascii() {
+    let pool: Pool<1> = Pool::new();
+
+    for string in ["GET", "content-type", "", &"-".repeat(200)] {
+        assert_eq!(pool.intern_ascii(string.as_bytes()), Ok(pool.intern(string)));
+    }
+
+    assert_eq!(pool.intern_ascii("héllo".as_bytes()), Err(NonAsciiError { position: 1 }));
+    assert_eq!(pool.intern_ascii(b"abc\xff"), Err(NonAsciiError { position: 3 }));
+    assert_eq!(pool.find("abc"), None);
+}
+
+#[test]
+fn intern_redacted() {
+    fn redact_digits(string: &str) -> Cow<'_, str> {
+        if !string.bytes().any(|b| b.is_ascii_digit()) {
+            return Cow::Borrowed(string);
+        }
+
+        let mut redacted = String::new();
+        for c in string.chars() {
+            match c.is_ascii_digit() {
+                true if redacted.ends_with('#') => (),
+                true => redacted.push('#'),
+                false => redacted.push(c),
+            }
+        }
+
+        Cow::Owned(redacted)
+    }
+
+    let pool: Pool<1> = Pool::new();
+    let a = pool.intern_redacted("user_1234_card_5678", redact_digits);
+    let b = pool.intern_redacted("user_99_card_1", redact_digits);
+    assert_eq!(&*a, "user_#_card_#");
+    assert_eq!(a, b);
+
+    let plain = pool.intern("no_digits");
+    let unchanged = pool.intern_redacted("no_digits", redact_digits);
+    assert_eq!(unchanged.raw_slot().unwrap().as_ptr(), plain.raw_slot().unwrap().as_ptr());
+}
+
+#[test]
+fn intern_normalize_newlines() {
+    let pool: Pool<1> = Pool::new();
+    let unix = pool.intern("line 1\nline 2\n\nline 4\n");
+
+    let windows = pool.intern_normalize_newlines("line 1\r\nline 2\r\n\r\nline 4\r\n");
+    let mac = pool.intern_normalize_newlines("line 1\rline 2\r\rline 4\r");
+    let mixed = pool.intern_normalize_newlines("line 1\r\nline 2\n\rline 4\r");
+    assert_eq!(windows, unix);
+    assert_eq!(mac, unix);
+    assert_eq!(mixed, unix);
+
+    let no_cr = pool.intern_normalize_newlines("line 1\nline 2\n\nline 4\n");
+    assert_eq!(no_cr.raw_slot().unwrap().as_ptr(), unix.raw_slot().unwrap().as_ptr());
+}
+
+#[test]
+fn intern_trimmed_lowercase() {
+    let pool: Pool<1> = Pool::new();
+    let expected = pool.intern("content-type");
+
+    for string in [" Content-Type ", "content-type", "\tCONTENT-TYPE", "content-Type\r\n", "  content-type"] {
+        assert_eq!(pool.intern_trimmed_lowercase(string), expected);
+    }
+
+    let unchanged = pool.intern_trimmed_lowercase(" content-type ");
+    assert_eq!(unchanged.raw_slot().unwrap().as_ptr(), expected.raw_slot().unwrap().as_ptr());
+}
+
+#[test]
+fn intern_escaped() {
+    use Escaper::*;
+
+    let pool: Pool<1> = Pool::new();
+
+    let plain = pool.intern("user_id");
+    let unchanged = pool.intern_escaped("user_id", SqlIdentifier);
+    assert_eq!(unchanged.raw_slot().unwrap().as_ptr(), plain.raw_slot().unwrap().as_ptr());
+    assert_eq!(&*pool.intern_escaped("user_id", Shell), "user_id");
+    assert_eq!(&*pool.intern_escaped("./a-b.txt", Shell), "./a-b.txt");
+
+    assert_eq!(&*pool.intern_escaped("1st", SqlIdentifier), "\"1st\"");
+    assert_eq!(&*pool.intern_escaped("user id", SqlIdentifier), "\"user id\"");
+    assert_eq!(&*pool.intern_escaped("say \"hi\"", SqlIdentifier), "\"say \"\"hi\"\"\"");
+    assert_eq!(&*pool.intern_escaped("", SqlIdentifier), "\"\"");
+
+    assert_eq!(&*pool.intern_escaped("a b", Shell), "'a b'");
+    assert_eq!(&*pool.intern_escaped("it's", Shell), "'it'\\''s'");
+    assert_eq!(&*pool.intern_escaped("$HOME", Shell), "'$HOME'");
+    assert_eq!(&*pool.intern_escaped("", Shell), "''");
+
+    // repeated identifiers are stored once
+    assert_eq!(pool.intern_escaped("user id", SqlIdentifier), pool.find("\"user id\"").unwrap());
+}
+
+#[test]
+fn intern_number() {
+    let pool: Pool<1> = Pool::new();
+    let one = pool.intern_number("1").unwrap();
+
+    for spelling in ["1.0", "1.00", "+1", "01", "1e0", "0.1e1"] {
+        assert_eq!(pool.intern_number(spelling), Ok(one.clone()));
+    }
+
+    assert_eq!(&*one, "1");
+    assert_eq!(&*pool.intern_number("-2.50").unwrap(), "-2.5");
+    assert_eq!(&*pool.intern_number("1e3").unwrap(), "1000");
+    assert_eq!(&*pool.intern_number("-0.0").unwrap(), "0");
+
+    for not_a_number in ["", "one", "1,0", "1.0.0", " 1"] {
+        assert!(matches!(pool.intern_number(not_a_number), Err(ParseNumberError::Invalid(_))));
+    }
+
+    for not_finite in ["NaN", "nan", "inf", "-infinity", "Infinity", "1e400"] {
+        assert_eq!(pool.intern_number(not_finite), Err(ParseNumberError::NotFinite));
+    }
+
+    assert_eq!(pool.len(), 4);
+}
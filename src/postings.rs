@@ -0,0 +1,70 @@
+use super::{Pool, PoolStr};
+use alloc::{vec::Vec, collections::BTreeMap};
+
+/// Pool keeping a list of positions for each string
+///
+/// Positions (e.g. document ids) are supplied when strings
+/// are interned, turning the pool into a small inverted index.
+#[derive(Debug, Clone)]
+pub struct InvertedIndex<const P: usize = 1, const S: usize = 1024> {
+    pool: Pool<P, S>,
+    postings: BTreeMap<PoolStr<P, S>, Vec<u32>>,
+}
+
+impl<const P: usize, const S: usize> InvertedIndex<P, S> {
+    /// Creates a new index with a new pool
+    pub fn new() -> Self {
+        Self::from_pool(Pool::new())
+    }
+
+    /// Creates a new index from an existing pool
+    ///
+    /// Strings already in the pool have no positions.
+    pub fn from_pool(pool: Pool<P, S>) -> Self {
+        Self {
+            pool,
+            postings: BTreeMap::new(),
+        }
+    }
+
+    /// The pool holding the strings of this index
+    pub fn pool(&self) -> &Pool<P, S> {
+        &self.pool
+    }
+
+    /// Interns `string` and appends `position` to its positions
+    pub fn intern_at(&mut self, string: &str, position: u32) -> PoolStr<P, S> {
+        let pool_str = self.pool.intern(string);
+        self.postings.entry(pool_str.clone()).or_default().push(position);
+        pool_str
+    }
+
+    /// Positions of `string`, in the order they were added
+    pub fn postings(&self, string: &str) -> Option<&[u32]> {
+        self.postings.get(string).map(Vec::as_slice)
+    }
+}
+
+impl<const P: usize, const S: usize> Default for InvertedIndex<P, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn inverted_index() {
+    let documents = ["the quick fox", "the lazy dog", "quick quick"];
+    let mut index: InvertedIndex<4> = InvertedIndex::new();
+
+    for (id, document) in documents.iter().enumerate() {
+        for word in document.split(' ') {
+            index.intern_at(word, id as u32);
+        }
+    }
+
+    assert_eq!(index.postings("the"), Some(&[0, 1][..]));
+    assert_eq!(index.postings("quick"), Some(&[0, 2, 2][..]));
+    assert_eq!(index.postings("dog"), Some(&[1][..]));
+    assert_eq!(index.postings("cat"), None);
+    assert_eq!(index.pool().len(), 5);
+}
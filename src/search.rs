@@ -0,0 +1,153 @@
+use super::{Pool, PoolStr, iter::Strings};
+use alloc::vec::Vec;
+
+// Levenshtein distance, in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = Vec::with_capacity(b.len() + 1);
+
+    for (i, a_char) in a.chars().enumerate() {
+        current.clear();
+        current.push(i + 1);
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + (a_char != *b_char) as usize;
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+            current.push(substitution.min(insertion).min(deletion));
+        }
+
+        core::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+// `*` matches any chars, `?` matches one char
+fn glob_matches(pattern: &str, string: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let string: Vec<char> = string.chars().collect();
+    let (mut p, mut s) = (0, 0);
+    // position of the last `*`, and of the string when it was reached
+    let mut star = None;
+
+    while s < string.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, s));
+                p += 1;
+            },
+            Some(c) if *c == '?' || *c == string[s] => {
+                p += 1;
+                s += 1;
+            },
+            // let the last `*` match one more char
+            _ => match star {
+                Some((star_p, star_s)) => {
+                    star = Some((star_p, star_s + 1));
+                    p = star_p + 1;
+                    s = star_s + 1;
+                },
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+impl<const P: usize, const S: usize> PoolStr<P, S> {
+    /// Checks if this string matches a glob `pattern`
+    ///
+    /// `*` matches any sequence of chars, `?` matches a single
+    /// char, and other chars match themselves.
+    pub fn matches_glob(&self, pattern: &str) -> bool {
+        glob_matches(pattern, self)
+    }
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Locates the strings matching a glob `pattern`
+    ///
+    /// See [`PoolStr::matches_glob`]. Every string of the pool
+    /// is compared; the empty string is never returned.
+    pub fn find_matching_glob(&self, pattern: &str) -> Vec<PoolStr<P, S>> {
+        self.iter_pool_str().filter(|pool_str| pool_str.matches_glob(pattern)).collect()
+    }
+
+    /// Locates `string`, or the closest string within `max_distance` edits
+    ///
+    /// The distance is the Levenshtein distance, in chars. When the
+    /// string isn't found, every string of the pool is compared.
+    pub fn find_or_closest(&self, string: &str, max_distance: usize) -> Option<PoolStr<P, S>> {
+        if let Some(pool_str) = self.find(string) {
+            return Some(pool_str);
+        }
+
+        let char_count = string.chars().count();
+        let mut closest = None;
+        let mut closest_distance = max_distance.saturating_add(1);
+
+        for candidate in Strings::new(self.inner()) {
+            // the distance is at least the difference in length
+            if candidate.chars().count().abs_diff(char_count) >= closest_distance {
+                continue;
+            }
+
+            let distance = edit_distance(string, candidate);
+            if distance < closest_distance {
+                closest = Some(candidate);
+                closest_distance = distance;
+            }
+        }
+
+        self.find(closest?)
+    }
+}
+
+#[test]
+fn find_or_closest() {
+    let pool: Pool<4> = Pool::new();
+
+    for string in ["listen_address", "listen_port", "log_level", "timeout"] {
+        pool.intern(string);
+    }
+
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(&*pool.find_or_closest("timeout", 0).unwrap(), "timeout");
+    assert_eq!(&*pool.find_or_closest("listen_prot", 2).unwrap(), "listen_port");
+    assert_eq!(&*pool.find_or_closest("log_levle", 2).unwrap(), "log_level");
+    assert_eq!(pool.find_or_closest("log_levle", 1), None);
+    assert_eq!(pool.find_or_closest("something_else", 3), None);
+    assert!(pool.find_or_closest("something_else", usize::MAX).is_some());
+}
+
+#[test]
+fn find_matching_glob() {
+    let pool: Pool<4> = Pool::new();
+
+    for string in ["user.name", "user.email", "users", "group.name", "héllo"] {
+        pool.intern(string);
+    }
+
+    let matching = |pattern| {
+        let mut strings = pool.find_matching_glob(pattern);
+        strings.sort();
+        strings
+    };
+
+    let empty: [&str; 0] = [];
+    assert_eq!(matching("user.*"), ["user.email", "user.name"]);
+    assert_eq!(matching("*.name"), ["group.name", "user.name"]);
+    assert_eq!(matching("user?"), ["users"]);
+    assert_eq!(matching("h?llo"), ["héllo"]);
+    assert_eq!(matching("u*.?a*e"), ["user.name"]);
+    assert_eq!(matching("*e*"), ["group.name", "user.email", "user.name", "users"]);
+    assert_eq!(matching("*"), ["group.name", "héllo", "user.email", "user.name", "users"]);
+    assert_eq!(matching("user"), empty);
+    assert_eq!(matching("user.name"), ["user.name"]);
+
+    assert!(PoolStr::<4>::empty().matches_glob("*"));
+    assert!(!PoolStr::<4>::empty().matches_glob("?"));
+}
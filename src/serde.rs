@@ -1,26 +1,65 @@
-use core::{sync::atomic::{Ordering::*, AtomicPtr}, fmt};
-use serde::{Serialize, Serializer, Deserialize, Deserializer, de::{Visitor, Error as DeError}};
-use super::{PoolCell, PoolStr};
+use core::{sync::atomic::{Ordering::*, AtomicPtr}, cell::Cell, ptr::null, fmt};
+use serde::{Serialize, Serializer, Deserialize, Deserializer, de::{Visitor, DeserializeSeed, SeqAccess, Error as DeError}};
+use serde::ser::SerializeSeq;
+use std::collections::BTreeMap;
+use super::{Pool, PoolCell, PoolStr};
 
 static POOL_CELL: AtomicPtr<PoolCell<0>> = AtomicPtr::new(0usize as _);
 
+std::thread_local! {
+    static THREAD_POOL_CELL: Cell<*const PoolCell<0>> = const { Cell::new(null()) };
+}
+
 pub fn set_serde_pool<const P: usize>(pool_cell: &'static PoolCell<P>) {
     POOL_CELL.store(pool_cell as *const _ as *mut _, Relaxed);
 }
 
+/// Sets the serde pool of the current thread
+///
+/// It takes precedence over the one set with [`set_serde_pool`],
+/// until [`clear_thread_serde_pool`] is called on this thread.
+pub fn set_thread_serde_pool<const P: usize>(pool_cell: &'static PoolCell<P>) {
+    THREAD_POOL_CELL.with(|cell| cell.set(pool_cell as *const _ as *const _));
+}
+
+/// Makes the current thread use the pool set with [`set_serde_pool`] again
+pub fn clear_thread_serde_pool() {
+    THREAD_POOL_CELL.with(|cell| cell.set(null()));
+}
+
 pub fn get_serde_pool<const P: usize>() -> &'static PoolCell<P> {
     let err = "Please set a pool for serde using strpool::serde::set_serde_pool";
-    let pool_cell = unsafe { POOL_CELL.load(Relaxed).as_ref() }.expect(err);
+    let thread_pool_cell = THREAD_POOL_CELL.with(|cell| cell.get());
+
+    let pool_cell_ptr = match thread_pool_cell.is_null() {
+        true => POOL_CELL.load(Relaxed) as *const PoolCell<0>,
+        false => thread_pool_cell,
+    };
+
+    let pool_cell = unsafe { pool_cell_ptr.as_ref() }.expect(err);
     if pool_cell.subpools == P {
-        unsafe { (POOL_CELL.load(Relaxed) as *const PoolCell<P>).as_ref() }.unwrap()
+        unsafe { (pool_cell_ptr as *const PoolCell<P>).as_ref() }.unwrap()
     } else {
         panic!("The current serde pool has a different subpools generic parameter")
     }
 }
 
+// binary formats get the bytes of the string
+fn deserialize_pool_str<'de, D: Deserializer<'de>, V: Visitor<'de>>(deserializer: D, visitor: V) -> Result<V::Value, D::Error> {
+    match deserializer.is_human_readable() {
+        true => deserializer.deserialize_str(visitor),
+        false => deserializer.deserialize_bytes(visitor),
+    }
+}
+
+fn utf8_from_bytes<'a, E: DeError, V: Visitor<'a>>(bytes: &'a [u8], visitor: &V) -> Result<&'a str, E> {
+    let unexpected = serde::de::Unexpected::Bytes(bytes);
+    core::str::from_utf8(bytes).map_err(|_| DeError::invalid_value(unexpected, visitor))
+}
+
 impl<'a, const P: usize> Deserialize<'a> for PoolStr<P> {
     fn deserialize<D: Deserializer<'a>>(deserializer: D) -> Result<Self, D::Error> {
-        deserializer.deserialize_str(PoolStrVisitor::<P>)
+        deserialize_pool_str(deserializer, PoolStrVisitor::<P>)
     }
 }
 
@@ -36,12 +75,316 @@ impl<'de, const P: usize> Visitor<'de> for PoolStrVisitor<P> {
     fn visit_str<E: DeError>(self, s: &str) -> Result<Self::Value, E> {
         Ok(get_serde_pool().pool().intern(s))
     }
+
+    fn visit_bytes<E: DeError>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        let string = utf8_from_bytes(bytes, &self)?;
+        self.visit_str(string)
+    }
+}
+
+/// Deserializes a [`PoolStr`] into a specific pool
+///
+/// Unlike the [`Deserialize`] implementation, this doesn't use
+/// the serde pool:
+///
+/// ```rust
+/// # use {strpool::{Pool, serde::PoolSeed}, serde::de::DeserializeSeed};
+/// let pool: Pool<4> = Pool::new();
+/// let mut deserializer = serde_json::Deserializer::from_str(r#""John Doe""#);
+/// let pool_str = PoolSeed(pool.clone()).deserialize(&mut deserializer).unwrap();
+/// assert_eq!(pool.find("John Doe"), Some(pool_str));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoolSeed<const P: usize>(pub Pool<P>);
+
+impl<'de, const P: usize> DeserializeSeed<'de> for PoolSeed<P> {
+    type Value = PoolStr<P>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserialize_pool_str(deserializer, self)
+    }
+}
+
+impl<'de, const P: usize> Visitor<'de> for PoolSeed<P> {
+    type Value = PoolStr<P>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a string")
+    }
+
+    fn visit_str<E: DeError>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(self.0.intern(s))
+    }
+
+    fn visit_bytes<E: DeError>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        let string = utf8_from_bytes(bytes, &self)?;
+        self.visit_str(string)
+    }
+}
+
+/// Deserializes a sequence of [`PoolStr`] into a specific pool
+///
+/// See [`PoolSeed`].
+#[derive(Debug, Clone)]
+pub struct PoolSeqSeed<const P: usize>(pub Pool<P>);
+
+impl<'de, const P: usize> DeserializeSeed<'de> for PoolSeqSeed<P> {
+    type Value = Vec<PoolStr<P>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, const P: usize> Visitor<'de> for PoolSeqSeed<P> {
+    type Value = Vec<PoolStr<P>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of strings")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut pool_strs = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+        while let Some(pool_str) = seq.next_element_seed(PoolSeed(self.0.clone()))? {
+            pool_strs.push(pool_str);
+        }
+
+        Ok(pool_strs)
+    }
 }
 
 impl<const P: usize> Serialize for PoolStr<P> {
-    // Required method
+    // binary formats get the bytes of the string
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => serializer.serialize_str(self),
+            false => serializer.serialize_bytes(self.as_bytes()),
+        }
+    }
+}
+
+std::thread_local! {
+    // the PoolStrTable in scope, and its P parameter
+    static TABLE: Cell<(*mut (), usize)> = const { Cell::new((core::ptr::null_mut(), 0)) };
+}
+
+/// Side table of the strings serialized as ids
+///
+/// With `#[serde(with = "strpool::serde::id")]`, a [`PoolStr`] is
+/// serialized as a `u32` in the table of the current [`scope`](Self::scope).
+/// The table itself serializes as the sequence of its strings,
+/// and can be deserialized into a pool with [`PoolStrTableSeed`].
+///
+/// ```rust
+/// # use {strpool::{Pool, PoolStr, serde::{PoolStrTable, PoolStrTableSeed}}, serde::de::DeserializeSeed};
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Edge(#[serde(with = "strpool::serde::id")] PoolStr<4>, #[serde(with = "strpool::serde::id")] PoolStr<4>);
+///
+/// let pool: Pool<4> = Pool::new();
+/// let edges = vec![Edge(pool.intern("a"), pool.intern("b")), Edge(pool.intern("b"), pool.intern("a"))];
+///
+/// let mut table: PoolStrTable<4> = PoolStrTable::new();
+/// let edges_json = table.scope(|| serde_json::to_string(&edges)).unwrap();
+/// let table_json = serde_json::to_string(&table).unwrap();
+/// assert_eq!((&*edges_json, &*table_json), ("[[0,1],[1,0]]", r#"["a","b"]"#));
+///
+/// let mut deserializer = serde_json::Deserializer::from_str(&table_json);
+/// let mut table = PoolStrTableSeed(pool.clone()).deserialize(&mut deserializer).unwrap();
+/// let edges: Vec<Edge> = table.scope(|| serde_json::from_str(&edges_json)).unwrap();
+/// assert_eq!(edges[1].0, pool.intern("b"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PoolStrTable<const P: usize> {
+    strings: Vec<PoolStr<P>>,
+    ids: BTreeMap<PoolStr<P>, u32>,
+}
+
+impl<const P: usize> PoolStrTable<P> {
+    /// Creates an empty table
+    pub fn new() -> Self {
+        Self {
+            strings: Vec::new(),
+            ids: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the id of `pool_str`, adding it to the table if needed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table already holds `u32::MAX` strings.
+    pub fn id(&mut self, pool_str: &PoolStr<P>) -> u32 {
+        if let Some(id) = self.ids.get(pool_str) {
+            return *id;
+        }
+
+        let id = u32::try_from(self.strings.len()).expect("PoolStrTable: too many strings");
+        self.strings.push(pool_str.clone());
+        self.ids.insert(pool_str.clone(), id);
+        id
+    }
+
+    /// Returns the string of an id
+    pub fn resolve(&self, id: u32) -> Option<&PoolStr<P>> {
+        self.strings.get(id as usize)
+    }
+
+    /// Number of strings in the table
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Checks if the table holds no string
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Runs `f` with this table as the one used by [`id`] on this thread
+    ///
+    /// Scopes can be nested; the previous table is restored afterwards.
+    pub fn scope<R, F: FnOnce() -> R>(&mut self, f: F) -> R {
+        struct Restore((*mut (), usize));
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                TABLE.with(|table| table.set(self.0));
+            }
+        }
+
+        let previous = TABLE.with(|table| table.replace((self as *mut Self as *mut (), P)));
+        let _restore = Restore(previous);
+        f()
+    }
+
+    fn with_current<R>(f: impl FnOnce(&mut Self) -> R) -> Option<R> {
+        let (ptr, subpools) = TABLE.with(|table| table.get());
+
+        if subpools != P {
+            return None;
+        }
+
+        // set by scope(), which holds a mutable borrow
+        unsafe { (ptr as *mut Self).as_mut() }.map(f)
+    }
+}
+
+impl<const P: usize> Serialize for PoolStrTable<P> {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        serializer.serialize_str(&*self)
+        let mut output = serializer.serialize_seq(Some(self.strings.len()))?;
+
+        for pool_str in &self.strings {
+            output.serialize_element(pool_str)?;
+        }
+
+        output.end()
+    }
+}
+
+/// Deserializes a [`PoolStrTable`] into a specific pool
+#[derive(Debug, Clone)]
+pub struct PoolStrTableSeed<const P: usize>(pub Pool<P>);
+
+impl<'de, const P: usize> DeserializeSeed<'de> for PoolStrTableSeed<P> {
+    type Value = PoolStrTable<P>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut table = PoolStrTable::new();
+
+        for pool_str in PoolSeqSeed(self.0).deserialize(deserializer)? {
+            table.id(&pool_str);
+        }
+
+        Ok(table)
+    }
+}
+
+/// Representation as an id in a [`PoolStrTable`]
+///
+/// Both functions fail outside of [`PoolStrTable::scope`].
+pub mod id {
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+    use serde::{ser::Error as SerError, de::Error as DeError};
+    use super::{PoolStr, PoolStrTable};
+
+    const NO_TABLE: &str = "no PoolStrTable in scope with the same subpools generic parameter";
+
+    pub fn serialize<S: Serializer, const P: usize>(pool_str: &PoolStr<P>, serializer: S) -> Result<S::Ok, S::Error> {
+        let id = PoolStrTable::with_current(|table| table.id(pool_str));
+        id.ok_or_else(|| SerError::custom(NO_TABLE))?.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const P: usize>(deserializer: D) -> Result<PoolStr<P>, D::Error> {
+        let id = u32::deserialize(deserializer)?;
+        let pool_str = PoolStrTable::with_current(|table| table.resolve(id).cloned());
+
+        match pool_str {
+            Some(Some(pool_str)) => Ok(pool_str),
+            Some(None) => Err(DeError::custom(format_args!("unknown PoolStr id {}", id))),
+            None => Err(DeError::custom(NO_TABLE)),
+        }
+    }
+}
+
+/// Verbose representation, for tooling and debugging
+///
+/// With `#[serde(with = "strpool::serde::verbose")]`, a [`PoolStr`] is
+/// serialized as `{ "s": "...", "tier": "small" }`, where `tier` is
+/// either `empty`, `small` or `large`. Deserialization interns the
+/// `s` field into the serde pool and ignores `tier`.
+pub mod verbose {
+    use core::fmt;
+    use serde::{Serializer, Deserializer, ser::SerializeStruct};
+    use serde::de::{Visitor, MapAccess, SeqAccess, IgnoredAny, Error as DeError};
+    use super::PoolStr;
+
+    const FIELDS: &[&str] = &["s", "tier"];
+
+    pub fn serialize<S: Serializer, const P: usize>(pool_str: &PoolStr<P>, serializer: S) -> Result<S::Ok, S::Error> {
+        let tier = match (pool_str.is_empty(), pool_str.raw_slot()) {
+            (true, _) => "empty",
+            (false, Some(_)) => "small",
+            (false, None) => "large",
+        };
+
+        let mut output = serializer.serialize_struct("PoolStr", 2)?;
+        output.serialize_field("s", pool_str)?;
+        output.serialize_field("tier", tier)?;
+        output.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const P: usize>(deserializer: D) -> Result<PoolStr<P>, D::Error> {
+        deserializer.deserialize_struct("PoolStr", FIELDS, VerboseVisitor::<P>)
+    }
+
+    struct VerboseVisitor<const P: usize>;
+
+    impl<'de, const P: usize> Visitor<'de> for VerboseVisitor<P> {
+        type Value = PoolStr<P>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "a verbose PoolStr")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut pool_str = None;
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "s" => pool_str = Some(map.next_value::<PoolStr<P>>()?),
+                    _ => _ = map.next_value::<IgnoredAny>()?,
+                }
+            }
+
+            pool_str.ok_or_else(|| DeError::missing_field("s"))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let err = || DeError::invalid_length(0, &self);
+            let pool_str = seq.next_element::<PoolStr<P>>()?.ok_or_else(err)?;
+            seq.next_element::<IgnoredAny>()?;
+            Ok(pool_str)
+        }
     }
 }
 
@@ -69,3 +412,322 @@ fn test_serde() {
 
     println!("SERDE: {:#?}", POOL);
 }
+
+#[test]
+fn test_serde_verbose() {
+    use serde::{Serialize, Deserialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Test {
+        #[serde(with = "verbose")]
+        test1: PoolStr<16>,
+        #[serde(with = "verbose")]
+        test2: PoolStr<16>,
+    }
+
+    static POOL: PoolCell<16> = PoolCell::new();
+
+    set_serde_pool(&POOL);
+
+    let data = r#"{"test1":{"s":"John Doe","tier":"small"},"test2":{"s":"","tier":"empty"}}"#;
+    let p: Test = serde_json::from_str(data).unwrap();
+    assert_eq!(&*p.test1, "John Doe");
+    assert_eq!(data, &*serde_json::to_string(&p).unwrap());
+}
+
+#[test]
+fn test_thread_serde_pool() {
+    static POOL_A: PoolCell<4> = PoolCell::new();
+    static POOL_B: PoolCell<8> = PoolCell::new();
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            set_thread_serde_pool(&POOL_A);
+            let pool_str: PoolStr<4> = serde_json::from_str(r#""thread a""#).unwrap();
+            assert_eq!(POOL_A.pool().find("thread a"), Some(pool_str));
+            clear_thread_serde_pool();
+        });
+
+        scope.spawn(|| {
+            set_thread_serde_pool(&POOL_B);
+            let pool_str: PoolStr<8> = serde_json::from_str(r#""thread b""#).unwrap();
+            assert_eq!(POOL_B.pool().find("thread b"), Some(pool_str));
+        });
+    });
+
+    assert_eq!(POOL_A.pool().find("thread b"), None);
+    assert_eq!(POOL_B.pool().find("thread a"), None);
+}
+
+#[test]
+fn test_pool_seed() {
+    let pool_a: Pool<4> = Pool::new();
+    let pool_b: Pool<4> = Pool::new();
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#"["a", "b", "a"]"#);
+    let pool_strs = PoolSeqSeed(pool_a.clone()).deserialize(&mut deserializer).unwrap();
+    assert_eq!(pool_strs, ["a", "b", "a"]);
+    assert_eq!(pool_a.len(), 2);
+
+    let mut deserializer = serde_json::Deserializer::from_str(r#""c""#);
+    let pool_str = PoolSeed(pool_b.clone()).deserialize(&mut deserializer).unwrap();
+    assert_eq!(pool_b.find("c"), Some(pool_str));
+    assert_eq!(pool_a.find("c"), None);
+
+    let mut deserializer = serde_json::Deserializer::from_str("5");
+    assert!(PoolSeed(pool_b).deserialize(&mut deserializer).is_err());
+}
+
+#[test]
+fn test_pool_str_table() {
+    #[derive(Serialize, Deserialize)]
+    struct Node {
+        #[serde(with = "id")]
+        name: PoolStr<4>,
+        #[serde(with = "id")]
+        parent: PoolStr<4>,
+    }
+
+    let pool: Pool<4> = Pool::new();
+    let node = |name, parent| Node { name: pool.intern(name), parent: pool.intern(parent) };
+    let nodes = [node("root", ""), node("a", "root"), node("b", "root")];
+
+    let mut table: PoolStrTable<4> = PoolStrTable::new();
+    let nodes_json = table.scope(|| serde_json::to_string(&nodes)).unwrap();
+    assert_eq!(nodes_json, r#"[{"name":0,"parent":1},{"name":2,"parent":0},{"name":3,"parent":0}]"#);
+    assert_eq!(serde_json::to_string(&table).unwrap(), r#"["root","","a","b"]"#);
+
+    // ids resolve into another pool
+    let other: Pool<4> = Pool::new();
+    let table_json = serde_json::to_string(&table).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&table_json);
+    let mut table = PoolStrTableSeed(other.clone()).deserialize(&mut deserializer).unwrap();
+    let nodes: Vec<Node> = table.scope(|| serde_json::from_str(&nodes_json)).unwrap();
+    assert_eq!((&*nodes[2].name, &*nodes[2].parent), ("b", "root"));
+    assert_eq!(other.find("b"), Some(nodes[2].name.clone()));
+
+    // outside of a scope, or with unknown ids
+    assert!(serde_json::to_string(&nodes).is_err());
+    let unknown: Result<Vec<Node>, _> = table.scope(|| serde_json::from_str(r#"[{"name":9,"parent":0}]"#));
+    assert!(unknown.is_err());
+}
+
+#[test]
+fn test_serde_bytes() {
+    use serde::de::value::{BytesDeserializer, Error};
+
+    let pool: Pool<4> = Pool::new();
+
+    let deserializer = BytesDeserializer::<Error>::new("héllo".as_bytes());
+    let pool_str = PoolSeed(pool.clone()).deserialize(deserializer).unwrap();
+    assert_eq!(pool.find("héllo"), Some(pool_str));
+
+    let deserializer = BytesDeserializer::<Error>::new(b"abc\xff");
+    assert!(PoolSeed(pool.clone()).deserialize(deserializer).is_err());
+    assert_eq!(pool.len(), 1);
+}
+
+#[test]
+fn test_binary_round_trip() {
+    use binary::Token;
+
+    let pool: Pool<4> = Pool::new();
+    let mut table: PoolStrTable<4> = PoolStrTable::new();
+    table.id(&pool.intern("héllo"));
+    table.id(&pool.intern(""));
+
+    let tokens = table.serialize(binary::Serializer).unwrap();
+    assert_eq!(tokens, Token::Seq(vec![Token::Bytes("héllo".into()), Token::Bytes(Vec::new())]));
+
+    let other: Pool<4> = Pool::new();
+    let mut table = PoolStrTableSeed(other.clone()).deserialize(tokens).unwrap();
+    assert_eq!(table.id(&other.intern("héllo")), 0);
+    assert_eq!(other.len(), 1);
+
+    static POOL: PoolCell<4> = PoolCell::new();
+    set_thread_serde_pool(&POOL);
+
+    let tokens = verbose::serialize(&pool.intern("héllo"), binary::Serializer).unwrap();
+    let fields = vec![("s", Token::Bytes("héllo".into())), ("tier", Token::Str("small".into()))];
+    assert_eq!(tokens, Token::Struct(fields));
+
+    let pool_str: PoolStr<4> = verbose::deserialize(tokens).unwrap();
+    assert_eq!(POOL.pool().find("héllo"), Some(pool_str));
+    clear_thread_serde_pool();
+}
+
+// minimal binary format, telling strings and bytes apart
+#[cfg(test)]
+mod binary {
+    use serde::{ser, de, forward_to_deserialize_any};
+    use serde::ser::{Impossible, Error as _};
+    use serde::de::value::Error;
+
+    #[derive(Debug, PartialEq)]
+    pub enum Token {
+        Str(String),
+        Bytes(Vec<u8>),
+        Seq(Vec<Token>),
+        Struct(Vec<(&'static str, Token)>),
+    }
+
+    pub struct Serializer;
+
+    pub struct Compound<T>(Vec<T>);
+
+    macro_rules! unsupported {
+        ($($method:ident($($arg:ty),*) -> $ret:ty;)*) => {
+            $(fn $method(self, $(_: $arg),*) -> Result<$ret, Error> {
+                Err(Error::custom(stringify!($method)))
+            })*
+        };
+    }
+
+    impl ser::Serializer for Serializer {
+        type Ok = Token;
+        type Error = Error;
+        type SerializeSeq = Compound<Token>;
+        type SerializeTuple = Impossible<Token, Error>;
+        type SerializeTupleStruct = Impossible<Token, Error>;
+        type SerializeTupleVariant = Impossible<Token, Error>;
+        type SerializeMap = Impossible<Token, Error>;
+        type SerializeStruct = Compound<(&'static str, Token)>;
+        type SerializeStructVariant = Impossible<Token, Error>;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn serialize_str(self, v: &str) -> Result<Token, Error> {
+            Ok(Token::Str(v.into()))
+        }
+
+        fn serialize_bytes(self, v: &[u8]) -> Result<Token, Error> {
+            Ok(Token::Bytes(v.into()))
+        }
+
+        fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+            Ok(Compound(Vec::with_capacity(len.unwrap_or(0))))
+        }
+
+        fn serialize_struct(self, _: &'static str, len: usize) -> Result<Self::SerializeStruct, Error> {
+            Ok(Compound(Vec::with_capacity(len)))
+        }
+
+        fn serialize_some<T: ?Sized + ser::Serialize>(self, _: &T) -> Result<Token, Error> {
+            Err(Error::custom("serialize_some"))
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + ser::Serialize>(self, _: &'static str, _: &T) -> Result<Token, Error> {
+            Err(Error::custom("serialize_newtype_struct"))
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + ser::Serialize>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<Token, Error> {
+            Err(Error::custom("serialize_newtype_variant"))
+        }
+
+        unsupported! {
+            serialize_bool(bool) -> Token;
+            serialize_i8(i8) -> Token;
+            serialize_i16(i16) -> Token;
+            serialize_i32(i32) -> Token;
+            serialize_i64(i64) -> Token;
+            serialize_u8(u8) -> Token;
+            serialize_u16(u16) -> Token;
+            serialize_u32(u32) -> Token;
+            serialize_u64(u64) -> Token;
+            serialize_f32(f32) -> Token;
+            serialize_f64(f64) -> Token;
+            serialize_char(char) -> Token;
+            serialize_none() -> Token;
+            serialize_unit() -> Token;
+            serialize_unit_struct(&'static str) -> Token;
+            serialize_unit_variant(&'static str, u32, &'static str) -> Token;
+            serialize_tuple(usize) -> Self::SerializeTuple;
+            serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+            serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+            serialize_map(Option<usize>) -> Self::SerializeMap;
+            serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+        }
+    }
+
+    impl ser::SerializeSeq for Compound<Token> {
+        type Ok = Token;
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            self.0.push(value.serialize(Serializer)?);
+            Ok(())
+        }
+
+        fn end(self) -> Result<Token, Error> {
+            Ok(Token::Seq(self.0))
+        }
+    }
+
+    impl ser::SerializeStruct for Compound<(&'static str, Token)> {
+        type Ok = Token;
+        type Error = Error;
+
+        fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+            self.0.push((key, value.serialize(Serializer)?));
+            Ok(())
+        }
+
+        fn end(self) -> Result<Token, Error> {
+            Ok(Token::Struct(self.0))
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for Token {
+        type Error = Error;
+
+        fn is_human_readable(&self) -> bool {
+            false
+        }
+
+        fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self {
+                Token::Str(string) => visitor.visit_string(string),
+                Token::Bytes(bytes) => visitor.visit_byte_buf(bytes),
+                Token::Seq(tokens) => visitor.visit_seq(Elements(tokens.into_iter())),
+                Token::Struct(fields) => visitor.visit_map(Fields(fields.into_iter(), None)),
+            }
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    struct Elements(std::vec::IntoIter<Token>);
+
+    impl<'de> de::SeqAccess<'de> for Elements {
+        type Error = Error;
+
+        fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+            self.0.next().map(|token| seed.deserialize(token)).transpose()
+        }
+    }
+
+    struct Fields(std::vec::IntoIter<(&'static str, Token)>, Option<Token>);
+
+    impl<'de> de::MapAccess<'de> for Fields {
+        type Error = Error;
+
+        fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+            let Some((key, value)) = self.0.next() else {
+                return Ok(None);
+            };
+
+            self.1 = Some(value);
+            seed.deserialize(Token::Str(key.into())).map(Some)
+        }
+
+        fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+            seed.deserialize(self.1.take().ok_or_else(|| <Error as de::Error>::custom("value before key"))?)
+        }
+    }
+}
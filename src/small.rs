@@ -1,40 +1,61 @@
 use core::sync::atomic::{Ordering::*, AtomicPtr, AtomicU8};
-use alloc::alloc::{Layout, alloc, dealloc};
-use core::mem::size_of;
+use alloc::alloc::{Layout, GlobalAlloc};
+use core::{mem::size_of, slice::{from_raw_parts, from_raw_parts_mut}};
 
-use super::{PoolInner, PoolStr, string_from_len_u8};
+use super::{PoolInner, PoolStr, MaybeStr, InternError, bytes_from_len_u8, integrity::IntegrityError, SMALL_MAX};
 
-const PAGE_SIZE: usize = 1024;
-const PAGE_ALIGN_MASK: usize = !(PAGE_SIZE - 1);
-const PAGE_CAPACITY: usize = PAGE_SIZE - size_of::<PageHeader<0>>(); // P doesn't influence PageHeader's size
-const PAGE_LAYOUT: Layout = unsafe { Layout::from_size_align_unchecked(PAGE_SIZE, PAGE_SIZE) };
+const HEADER_SIZE: usize = size_of::<PageHeader<0, 0>>(); // P and S don't influence PageHeader's size
 const NOT_READY: u8 = 0x80;
 const LEN_MASK: u8 = 0x7f;
 
 #[derive(Debug)]
-struct PageHeader<const P: usize> {
-    next: AtomicPtr<Page<P>>,
-    pool: *const PoolInner<P>,
+struct PageHeader<const P: usize, const S: usize> {
+    next: AtomicPtr<Page<P, S>>,
+    pool: *const PoolInner<P, S>,
 }
 
-pub(crate) struct Page<const P: usize> {
-    header: PageHeader<P>,
-    entries: [u8; PAGE_CAPACITY],
+// pages are S bytes long and aligned to S, so that the
+// header of a page can be found from any of its entries
+#[repr(C)]
+pub(crate) struct Page<const P: usize, const S: usize> {
+    header: PageHeader<P, S>,
+    // followed by the entries, up to the end of the page
 }
 
-impl<const P: usize> Page<P> {
-    fn find(&self, slice: &[u8]) -> Option<PoolStr<P>> {
+impl<const P: usize, const S: usize> Page<P, S> {
+    // evaluated at compile time when a pool is created
+    pub(crate) const VALID_S: () = assert!(
+        S.is_power_of_two() && S >= HEADER_SIZE + 1 + SMALL_MAX,
+        "the page size must be a power of two, large enough for SMALL_MAX",
+    );
+
+    const CAPACITY: usize = S - HEADER_SIZE;
+    const ALIGN_MASK: usize = !(S - 1);
+    // valid as checked by VALID_S
+    const LAYOUT: Layout = unsafe { Layout::from_size_align_unchecked(S, S) };
+
+    fn entries(&self) -> &[u8] {
+        let start = (self as *const Self).wrapping_add(1) as *const u8;
+        unsafe { from_raw_parts(start, Self::CAPACITY) }
+    }
+
+    fn entries_mut(&mut self) -> &mut [u8] {
+        let start = (self as *mut Self).wrapping_add(1) as *mut u8;
+        unsafe { from_raw_parts_mut(start, Self::CAPACITY) }
+    }
+
+    fn find(&self, slice: &[u8]) -> Option<&u8> {
         let mut i = 0;
-        while i < PAGE_CAPACITY {
-            let (len, ready) = read_atomic_slot_len(&self.entries[i]);
+        while i < Self::CAPACITY {
+            let (len, ready) = read_atomic_slot_len(&self.entries()[i]);
             // skip len byte:
             i += 1;
 
             if ready {
                 if len == slice.len() {
                     let j = i + len;
-                    if &self.entries[i..j] == slice {
-                        return Some(PoolStr::new(&self.entries[i - 1]));
+                    if &self.entries()[i..j] == slice {
+                        return Some(&self.entries()[i - 1]);
                     }
                 } else if len == 0 {
                     break;
@@ -48,23 +69,25 @@ impl<const P: usize> Page<P> {
         None
     }
 
-    fn try_intern(&mut self, slice: &[u8]) -> Option<PoolStr<P>> {
+    // returns the index of the slot; the boolean
+    // is true if the string was newly stored
+    fn try_intern(&mut self, slice: &[u8]) -> Option<(usize, bool)> {
         let mut i = 0;
-        while i < PAGE_CAPACITY {
-            let (len, ready) = read_atomic_slot_len(&self.entries[i]);
+        while i < Self::CAPACITY {
+            let (len, ready) = read_atomic_slot_len(&self.entries()[i]);
             let s = i + 1;
 
             if ready {
                 if len == slice.len() {
                     // same length... does this entry correspond to an equal string?
                     let e = s + len;
-                    if &self.entries[s..e] == slice {
+                    if &self.entries()[s..e] == slice {
                         // yes; we'll re-use it then
-                        return Some(PoolStr::new(&self.entries[i]));
+                        return Some((i, false));
                     }
                 } else if len == 0 {
                     // this entry is available
-                    if s + slice.len() <= PAGE_CAPACITY {
+                    if s + slice.len() <= Self::CAPACITY {
                         // there is enough space, meaning `string` isn't
                         // present in next pages (except if another thread
                         // was trying to intern the same string at the same
@@ -74,15 +97,15 @@ impl<const P: usize> Page<P> {
                         let slice_len = slice.len();
                         let len = slice_len as u8 | NOT_READY;
 
-                        if try_set_len(&self.entries[i], 0, len) {
+                        if try_set_len(&self.entries()[i], 0, len) {
                             // the NOT_READY flag is set, we can copy the bytes
                             let j = s + slice_len;
-                            self.entries[s..j].copy_from_slice(slice);
+                            self.entries_mut()[s..j].copy_from_slice(slice);
 
                             // remove NOT_READY flag
-                            assert!(try_set_len(&self.entries[i], len, len & LEN_MASK));
+                            assert!(try_set_len(&self.entries()[i], len, len & LEN_MASK));
 
-                            return Some(PoolStr::new(&self.entries[i]));
+                            return Some((i, true));
                         } else {
                             // retry this entry
                             continue;
@@ -100,15 +123,62 @@ impl<const P: usize> Page<P> {
         None
     }
 
+    // stores `a` and `b` in consecutive slots, without looking for
+    // them. returns None if there isn't enough space in this page,
+    // or (a, None) if another thread took the slot after `a`
+    fn try_intern_pair(&mut self, a: &[u8], b: &[u8]) -> Option<(PoolStr<P, S>, Option<PoolStr<P, S>>)> {
+        let mut i = 0;
+        while i < Self::CAPACITY {
+            let (len, ready) = read_atomic_slot_len(&self.entries()[i]);
+            let s = i + 1;
+
+            if ready && len == 0 {
+                // slot of `b`
+                let j = s + a.len();
+                if j + 1 + b.len() > Self::CAPACITY {
+                    // to next page
+                    break;
+                }
+
+                let a_len = a.len() as u8 | NOT_READY;
+                if !try_set_len(&self.entries()[i], 0, a_len) {
+                    // retry this entry
+                    continue;
+                }
+
+                let b_len = b.len() as u8 | NOT_READY;
+                let b_reserved = try_set_len(&self.entries()[j], 0, b_len);
+
+                self.entries_mut()[s..j].copy_from_slice(a);
+                assert!(try_set_len(&self.entries()[i], a_len, a_len & LEN_MASK));
+                let a_str = PoolStr::new(&self.entries()[i]);
+
+                if !b_reserved {
+                    return Some((a_str, None));
+                }
+
+                let k = j + 1 + b.len();
+                self.entries_mut()[(j + 1)..k].copy_from_slice(b);
+                assert!(try_set_len(&self.entries()[j], b_len, b_len & LEN_MASK));
+
+                return Some((a_str, Some(PoolStr::new(&self.entries()[j]))));
+            }
+
+            i = s + len;
+        }
+
+        None
+    }
+
     // used by Debug for Page
-    fn debug_slot(&self, len_index: usize) -> Option<(Option<&str>, usize)> {
-        if len_index < PAGE_CAPACITY {
-            let len_u8_ref = &self.entries[len_index];
+    fn debug_slot(&self, len_index: usize) -> Option<(Option<&[u8]>, usize)> {
+        if len_index < Self::CAPACITY {
+            let len_u8_ref = &self.entries()[len_index];
             let (len, ready) = read_atomic_slot_len(len_u8_ref);
 
             if len != 0 {
                 let string = match ready {
-                    true => Some(string_from_len_u8::<P>(len_u8_ref)),
+                    true => Some(bytes_from_len_u8::<P, S>(len_u8_ref)),
                     false => None,
                 };
 
@@ -122,94 +192,280 @@ impl<const P: usize> Page<P> {
     }
 }
 
-impl<const P: usize> PoolInner<P> {
-    pub(crate) fn find_small(&self, string: &str) -> Option<PoolStr<P>> {
-        let slice = string.as_bytes();
-        let pool_index = Self::index_for(string);
-        let mut ptr = self.first_page[pool_index].load(Relaxed);
+impl<const P: usize, const S: usize> PoolInner<P, S> {
+    pub(crate) fn find_small_slot(&self, slice: &[u8]) -> Option<&u8> {
+        self.find_small_slot_at(slice, self.index_for(slice))
+    }
+
+    pub(crate) fn find_small_slot_hashed(&self, slice: &[u8], hash: u64) -> Option<&u8> {
+        self.find_small_slot_at(slice, self.index_for_hashed(slice, hash))
+    }
+
+    fn find_small_slot_at(&self, slice: &[u8], pool_index: usize) -> Option<&u8> {
+        let mut ptr = self.first_page[pool_index].load(Acquire);
 
         while let Some(page) = unsafe { ptr.as_ref() } {
-            if let Some(pool_str) = page.find(slice) {
-                self.inc_ref_count();
-                return Some(pool_str);
+            if let Some(len_u8_ref) = page.find(slice) {
+                return Some(len_u8_ref);
             }
 
-            ptr = page.header.next.load(Relaxed);
+            ptr = page.header.next.load(Acquire);
         }
 
         None
     }
 
-    pub(crate) fn intern_small(&self, string: &str) -> PoolStr<P> {
-        let slice = string.as_bytes();
-        let pool_index = Self::index_for(string);
+    pub(crate) fn intern_small(&self, slice: &[u8]) -> Result<(&u8, bool), InternError> {
+        self.intern_small_at(slice, self.index_for(slice))
+    }
+
+    pub(crate) fn intern_small_hashed(&self, slice: &[u8], hash: u64) -> Result<(&u8, bool), InternError> {
+        self.intern_small_at(slice, self.index_for_hashed(slice, hash))
+    }
+
+    fn intern_small_at(&self, slice: &[u8], pool_index: usize) -> Result<(&u8, bool), InternError> {
         let mut page_ptr_ref = &self.first_page[pool_index];
 
         loop {
-            while let Some(page) = unsafe { page_ptr_ref.load(Relaxed).as_mut() } {
-                if let Some(pool_str) = page.try_intern(slice) {
+            while let Some(page) = unsafe { page_ptr_ref.load(Acquire).as_mut() } {
+                if let Some((index, new)) = page.try_intern(slice) {
                     self.inc_ref_count();
-                    return pool_str;
+                    return Ok((&page.entries()[index], new));
                 }
 
                 page_ptr_ref = &page.header.next;
             }
 
-            let last_searched_page_next_ptr_ref = page_ptr_ref;
+            // restart the search from the first unexplored page
+            self.append_page(page_ptr_ref)?;
+        }
+    }
+
+    // stores `a` and `b` in consecutive slots if possible;
+    // both must be small, new and in the same subpool.
+    pub(crate) fn intern_small_pair(&self, a: &str, b: &str) -> Result<(PoolStr<P, S>, PoolStr<P, S>), InternError> {
+        let pool_index = self.index_for(a.as_bytes());
+        let mut page_ptr_ref = &self.first_page[pool_index];
 
-            let new_page_ptr = unsafe {
-                let new_page_ptr = alloc(PAGE_LAYOUT) as *mut Page<P>;
+        loop {
+            while let Some(page) = unsafe { page_ptr_ref.load(Acquire).as_mut() } {
+                if let Some((a_str, b_str)) = page.try_intern_pair(a.as_bytes(), b.as_bytes()) {
+                    self.inc_ref_count();
+                    self.unique.fetch_add(1, SeqCst);
+
+                    let b_str = match b_str {
+                        Some(b_str) => {
+                            self.inc_ref_count();
+                            self.unique.fetch_add(1, SeqCst);
+                            b_str
+                        },
+                        // another thread took the slot after `a`
+                        None => PoolStr::new(self.get_or_intern_slot(b.as_bytes())?.unwrap().0),
+                    };
+
+                    return Ok((a_str, b_str));
+                }
 
-                let new_page = new_page_ptr.as_mut().unwrap();
-                new_page.header = PageHeader {
-                    next: AtomicPtr::new(0 as _),
-                    pool: self as _,
-                };
-                new_page.entries.fill(0);
+                page_ptr_ref = &page.header.next;
+            }
+
+            // restart the search from the first unexplored page
+            self.append_page(page_ptr_ref)?;
+        }
+    }
+
+    fn append_page(&self, mut page_ptr_ref: &AtomicPtr<Page<P, S>>) -> Result<(), InternError> {
+        let new_page_ptr = self.alloc_page()?;
+
+        loop {
+            // (re)try to append it
+            match page_ptr_ref.compare_exchange(0 as _, new_page_ptr, AcqRel, Acquire) {
+                Ok(_) => break Ok(()),
+                Err(new_page_ptr) => {
+                    // another thread appended a new page before we could do it
+                    // try to append the page we allocated to that new one
+                    let page = unsafe { new_page_ptr.as_ref() }.unwrap();
+                    page_ptr_ref = &page.header.next;
+                    // we have effectively pre-allocated a page for (much) later use
+                },
+            }
+        }
+    }
 
-                new_page_ptr
+    fn alloc_page(&self) -> Result<*mut Page<P, S>, InternError> {
+        unsafe {
+            let new_page_ptr = self.allocator.alloc(Page::<P, S>::LAYOUT) as *mut Page<P, S>;
+
+            let new_page = new_page_ptr.as_mut().ok_or(InternError::Alloc)?;
+            new_page.header = PageHeader {
+                next: AtomicPtr::new(0 as _),
+                pool: self as _,
             };
+            new_page.entries_mut().fill(0);
+            self.allocated.fetch_add(S, Relaxed);
+
+            Ok(new_page_ptr)
+        }
+    }
+
+    pub(crate) fn page_count(&self) -> usize {
+        (0..P).map(|pool_index| self.subpool_page_count(pool_index)).sum()
+    }
+
+    pub(crate) fn subpool_page_count(&self, pool_index: usize) -> usize {
+        let mut pages = 0;
+        let mut ptr = self.first_page[pool_index].load(Acquire);
 
-            // need to append a page
-            loop {
-                // (re)try to append it
-                match page_ptr_ref.compare_exchange(0 as _, new_page_ptr, SeqCst, Relaxed) {
-                    Ok(_) => break,
-                    Err(new_page_ptr) => {
-                        // another thread appended a new page before we could do it
-                        // try to append the page we allocated to that new one
-                        let page = unsafe { new_page_ptr.as_ref() }.unwrap();
-                        page_ptr_ref = &page.header.next;
-                        // we have effectively pre-allocated a page for (much) later use
-                    },
+        while let Some(page) = unsafe { ptr.as_ref() } {
+            pages += 1;
+            ptr = page.header.next.load(Acquire);
+        }
+
+        pages
+    }
+
+    pub(crate) fn small_slots(&self, pool_index: usize) -> SmallSlots<'_, P, S> {
+        SmallSlots {
+            page: unsafe { self.first_page[pool_index].load(Acquire).as_ref() },
+            index: 0,
+        }
+    }
+
+    // stores a copy of `string` in a new page without looking for
+    // an existing one, like two concurrent interns could do.
+    #[cfg(test)]
+    pub(crate) fn push_small_duplicate(&self, string: &str) {
+        let pool_index = self.index_for(string.as_bytes());
+        let mut page_ptr_ref = &self.first_page[pool_index];
+
+        while let Some(page) = unsafe { page_ptr_ref.load(Acquire).as_ref() } {
+            page_ptr_ref = &page.header.next;
+        }
+
+        let new_page_ptr = self.alloc_page().unwrap();
+        let new_page = unsafe { new_page_ptr.as_mut() }.unwrap();
+
+        new_page.try_intern(string.as_bytes());
+        page_ptr_ref.store(new_page_ptr, Release);
+    }
+
+    pub(crate) fn check_pages(&self, report: &mut dyn FnMut(IntegrityError)) {
+        for pool_index in 0..P {
+            let mut ptr = self.first_page[pool_index].load(Acquire);
+            let mut page_offset = 0;
+
+            while let Some(page) = unsafe { ptr.as_ref() } {
+                let mut error = |offset, description| report(IntegrityError {
+                    subpool: pool_index,
+                    offset: page_offset + offset,
+                    description,
+                });
+
+                if (ptr as usize) & !Page::<P, S>::ALIGN_MASK != 0 {
+                    error(0, "misaligned page");
                 }
+
+                if !core::ptr::eq(page.header.pool, self) {
+                    error(0, "page header points to another pool");
+                }
+
+                let mut i = 0;
+                while i < Page::<P, S>::CAPACITY {
+                    let (len, ready) = read_atomic_slot_len(&page.entries()[i]);
+
+                    if len == 0 {
+                        if !ready {
+                            error(i, "empty slot marked as not ready");
+                        }
+
+                        break;
+                    } else if i + 1 + len > Page::<P, S>::CAPACITY {
+                        error(i, "slot exceeds its page");
+                        break;
+                    }
+
+                    // a slot which isn't ready is being written
+                    // by another thread; its length is already set
+                    i += 1 + len;
+                }
+
+                page_offset += S;
+                ptr = page.header.next.load(Acquire);
             }
+        }
+    }
 
-            // restart the search from the first unexplored page
-            page_ptr_ref = last_searched_page_next_ptr_ref;
+    #[cfg(test)]
+    pub(crate) fn corrupt_first_page(&self, pool_index: usize) {
+        let page = unsafe { self.first_page[pool_index].load(Acquire).as_mut() }.unwrap();
+        page.header.pool = core::ptr::null();
+
+        // mark the first free slot as not ready
+        let (mut i, mut len) = (0, page.entries()[0]);
+        while len != 0 {
+            i += 1 + len as usize;
+            len = page.entries()[i];
         }
+
+        page.entries_mut()[i] = NOT_READY;
     }
 
     pub(crate) fn debug_pages(&self, output: &mut core::fmt::DebugList) {
         for pool_index in 0..P {
-            let mut ptr = self.first_page[pool_index].load(Relaxed);
+            let mut ptr = self.first_page[pool_index].load(Acquire);
 
             while let Some(page) = unsafe { ptr.as_ref() } {
                 output.entry(&page);
-                ptr = page.header.next.load(Relaxed);
+                ptr = page.header.next.load(Acquire);
+            }
+        }
+    }
+}
+
+/// Iterator over the ready slots of a subpool's pages
+pub(crate) struct SmallSlots<'a, const P: usize, const S: usize> {
+    page: Option<&'a Page<P, S>>,
+    index: usize,
+}
+
+impl<'a, const P: usize, const S: usize> Iterator for SmallSlots<'a, P, S> {
+    type Item = &'a u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let page = self.page?;
+
+            if self.index < Page::<P, S>::CAPACITY {
+                let len_u8_ref = &page.entries()[self.index];
+                let (len, ready) = read_atomic_slot_len(len_u8_ref);
+
+                if len != 0 {
+                    self.index += 1 + len;
+
+                    match ready {
+                        true => return Some(len_u8_ref),
+                        false => continue,
+                    }
+                }
             }
+
+            // to next page
+            self.page = unsafe { page.header.next.load(Acquire).as_ref() };
+            self.index = 0;
         }
     }
 }
 
 // returns (bytes_to_skip, ready)
+// the bytes of a ready slot were copied before its
+// length was released by try_set_len, so they're visible
 fn read_atomic_slot_len(len: &u8) -> (usize, bool) {
     let len = unsafe {
         (len as *const u8)
             .cast::<AtomicU8>()
             .as_ref()
             .unwrap()
-    }.load(Relaxed);
+    }.load(Acquire);
 
     match len & NOT_READY {
         0 => (len as usize, true),
@@ -225,34 +481,42 @@ fn try_set_len(len: &u8, prev: u8, new: u8) -> bool {
             .unwrap()
     };
 
-    len.compare_exchange(prev, new, SeqCst, Relaxed).is_ok()
+    len.compare_exchange(prev, new, AcqRel, Acquire).is_ok()
+}
+
+// the page of a slot, and the offset of the slot in its entries
+#[cfg(feature = "debug-internals")]
+pub(crate) fn slot_location<const P: usize, const S: usize>(len_u8_ptr: &u8) -> (*const (), usize) {
+    let addr_usize = (len_u8_ptr as *const _) as usize;
+    let page_ptr_usize = addr_usize & Page::<P, S>::ALIGN_MASK;
+    (page_ptr_usize as *const (), addr_usize - page_ptr_usize - HEADER_SIZE)
 }
 
-pub(crate) fn string_pool_ptr<const P: usize>(len_u8_ptr: &u8) -> *const PoolInner<P> {
+pub(crate) fn string_pool_ptr<const P: usize, const S: usize>(len_u8_ptr: &u8) -> *const PoolInner<P, S> {
     let addr_usize = (len_u8_ptr as *const _) as usize;
-    let page_ptr_usize = addr_usize & PAGE_ALIGN_MASK;
-    let page_ptr = page_ptr_usize as *const Page<P>;
+    let page_ptr_usize = addr_usize & Page::<P, S>::ALIGN_MASK;
+    let page_ptr = page_ptr_usize as *const Page<P, S>;
     let page = unsafe { page_ptr.as_ref() }.unwrap();
 
     page.header.pool
 }
 
-pub(crate) fn deep_drop<const P: usize>(mut ptr: *const Page<P>) {
+pub(crate) fn deep_drop<const P: usize, const S: usize>(mut ptr: *const Page<P, S>, allocator: &dyn GlobalAlloc) {
     while let Some(page) = unsafe { ptr.as_ref() } {
         let mut_ptr = (ptr as usize) as *mut u8;
         ptr = page.header.next.load(Relaxed);
-        unsafe { dealloc(mut_ptr, PAGE_LAYOUT) };
+        unsafe { allocator.dealloc(mut_ptr, Page::<P, S>::LAYOUT) };
     }
 }
 
-impl<const P: usize> core::fmt::Debug for Page<P> {
+impl<const P: usize, const S: usize> core::fmt::Debug for Page<P, S> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let mut output = f.debug_list();
         let mut i = 0;
 
         while let Some((string, next)) = self.debug_slot(i) {
             match string {
-                Some(string) => output.entry(&string),
+                Some(bytes) => output.entry(&MaybeStr(bytes)),
                 none => output.entry(&none),
             };
 
@@ -1,5 +1,5 @@
-use std::sync::Mutex;
-use super::Pool;
+use std::sync::{Mutex, OnceLock};
+use super::{Pool, PoolStr};
 
 #[derive(Debug)]
 pub struct PoolCell<const P: usize> {
@@ -7,6 +7,12 @@ pub struct PoolCell<const P: usize> {
     pub(crate) subpools: usize,
 }
 
+impl<const P: usize> Default for PoolCell<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const P: usize> PoolCell<P> {
     pub const fn new() -> Self {
         Self {
@@ -24,11 +30,174 @@ impl<const P: usize> PoolCell<P> {
     /// Retrieves the inner pool
     pub fn pool(&self) -> Pool<P> {
         let mut guard = self.pool.lock().expect("Couldn't lock Mutex");
+        guard.get_or_insert_with(Pool::new).clone()
+    }
+
+    /// Creates a new [`PoolStr`] in the inner pool, without cloning it
+    ///
+    /// This interns under the lock, so interns from several threads
+    /// wait for each other. An [`OncePoolCell`] doesn't lock.
+    pub fn intern(&self, string: &str) -> PoolStr<P> {
+        self.with_pool(|pool| pool.intern(string))
+    }
+
+    /// Locates an existing [`PoolStr`] in the inner pool, without cloning it
+    ///
+    /// See [`PoolCell::intern`].
+    pub fn find(&self, string: &str) -> Option<PoolStr<P>> {
+        self.with_pool(|pool| pool.find(string))
+    }
+
+    /// Runs `f` with the inner pool, locking only once
+    ///
+    /// The lock is held while `f` runs, so the pool isn't cloned
+    /// and [`PoolCell::swap`] blocks until `f` returns. `f` must
+    /// not use this cell, which would deadlock.
+    pub fn with_pool<R, F: FnOnce(&Pool<P>) -> R>(&self, f: F) -> R {
+        let mut guard = self.pool.lock().expect("Couldn't lock Mutex");
+        f(guard.get_or_insert_with(Pool::new))
+    }
+}
+
+/// [`PoolCell`] whose pool can't be swapped
+///
+/// After the first call, [`OncePoolCell::pool`] doesn't lock.
+#[derive(Debug)]
+pub struct OncePoolCell<const P: usize> {
+    pool: OnceLock<Pool<P>>,
+}
 
-        if guard.is_none() {
-            *guard = Some(Pool::new());
+impl<const P: usize> Default for OncePoolCell<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const P: usize> OncePoolCell<P> {
+    pub const fn new() -> Self {
+        Self {
+            pool: OnceLock::new(),
         }
+    }
+
+    /// Sets the inner pool, if it wasn't initialized yet
+    ///
+    /// Otherwise, `pool` is given back.
+    pub fn set(&self, pool: Pool<P>) -> Result<(), Pool<P>> {
+        self.pool.set(pool)
+    }
+
+    /// Retrieves the inner pool
+    pub fn pool(&self) -> Pool<P> {
+        self.pool.get_or_init(Pool::new).clone()
+    }
+
+    /// Runs `f` with the inner pool, without cloning it
+    pub fn with_pool<R, F: FnOnce(&Pool<P>) -> R>(&self, f: F) -> R {
+        f(self.pool.get_or_init(Pool::new))
+    }
 
-        guard.as_ref().unwrap().clone()
+    /// Creates a new [`PoolStr`] in the inner pool, without cloning it
+    pub fn intern(&self, string: &str) -> PoolStr<P> {
+        self.with_pool(|pool| pool.intern(string))
     }
+
+    /// Locates an existing [`PoolStr`] in the inner pool, without cloning it
+    pub fn find(&self, string: &str) -> Option<PoolStr<P>> {
+        self.with_pool(|pool| pool.find(string))
+    }
+}
+
+#[test]
+fn with_pool() {
+    use std::{sync::atomic::{AtomicBool, Ordering::SeqCst}, time::Duration};
+
+    static CELL: PoolCell<4> = PoolCell::new();
+    let swapped = AtomicBool::new(false);
+
+    let strings = std::thread::scope(|scope| CELL.with_pool(|pool| {
+        let before = pool.intern("before");
+
+        scope.spawn(|| {
+            CELL.swap(Pool::new());
+            swapped.store(true, SeqCst);
+        });
+
+        // swap() waits for the closure to return
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!swapped.load(SeqCst));
+
+        let after = pool.intern("after");
+        assert_eq!(pool.find("before"), Some(before.clone()));
+        [before, after]
+    }));
+
+    // both were interned in the pool replaced by swap()
+    assert!(swapped.load(SeqCst));
+    assert_eq!(strings[0].pool_ptr(), strings[1].pool_ptr());
+    assert_eq!(CELL.pool().find("before"), None);
+    assert_eq!(CELL.pool().find("after"), None);
+}
+
+#[test]
+fn once_pool_cell() {
+    static CELL: OncePoolCell<4> = OncePoolCell::new();
+
+    let strings: std::vec::Vec<_> = std::thread::scope(|scope| {
+        let threads: std::vec::Vec<_> = (0..4).map(|_| scope.spawn(|| CELL.pool().intern("shared"))).collect();
+        threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+    });
+
+    // every thread got the same pool
+    assert!(strings.iter().all(|pool_str| pool_str.pool_ptr() == strings[0].pool_ptr()));
+    assert!(CELL.set(Pool::new()).is_err());
+    assert_eq!(CELL.with_pool(|pool| pool.len()), 1);
+
+    let cell: OncePoolCell<4> = OncePoolCell::new();
+    assert!(cell.set(Pool::new()).is_ok());
+}
+
+#[test]
+fn intern_and_find() {
+    static CELL: PoolCell<4> = PoolCell::new();
+
+    assert_eq!(CELL.find("yikes"), None);
+    let yikes = CELL.intern("yikes");
+    assert_eq!(CELL.find("yikes"), Some(yikes.clone()));
+    assert_eq!(CELL.pool().find("yikes"), Some(yikes));
+
+    CELL.swap(Pool::new());
+    assert_eq!(CELL.find("yikes"), None);
+}
+
+#[test]
+fn concurrent_intern() {
+    use std::{format, vec::Vec};
+
+    static CELL: PoolCell<4> = PoolCell::new();
+    static ONCE_CELL: OncePoolCell<4> = OncePoolCell::new();
+
+    let strings: Vec<_> = (0..500).map(|i| format!("string number {}", i)).collect();
+
+    std::thread::scope(|scope| {
+        // each string is interned by one thread; concurrent
+        // interning of the same small string can store it twice
+        for offset in 0..4 {
+            let strings = &strings;
+            scope.spawn(move || {
+                for string in strings.iter().skip(offset).step_by(4) {
+                    assert_eq!(&*CELL.intern(string), string);
+                    assert_eq!(&*ONCE_CELL.intern(string), string);
+                }
+            });
+        }
+    });
+
+    for string in &strings {
+        assert_eq!(CELL.find(string).as_deref(), Some(&**string));
+        assert_eq!(ONCE_CELL.find(string).as_deref(), Some(&**string));
+    }
+
+    assert_eq!(CELL.pool().len(), strings.len());
+    assert_eq!(ONCE_CELL.pool().len(), strings.len());
 }
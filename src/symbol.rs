@@ -0,0 +1,208 @@
+use super::{Pool, PoolStr};
+use alloc::{vec::Vec, collections::BTreeMap, borrow::Cow};
+
+/// Compact identifier of a string in a [`SymbolMap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Index of this symbol in its map
+    pub fn id(self) -> u32 {
+        self.0
+    }
+}
+
+/// Two-way table between [`Symbol`]s and interned strings
+///
+/// Symbols are numbered from zero, in order of first appearance.
+#[derive(Debug, Clone)]
+pub struct SymbolMap<const P: usize = 1, const S: usize = 1024> {
+    strings: Vec<PoolStr<P, S>>,
+    symbols: BTreeMap<PoolStr<P, S>, Symbol>,
+}
+
+impl<const P: usize, const S: usize> SymbolMap<P, S> {
+    fn new() -> Self {
+        Self {
+            strings: Vec::new(),
+            symbols: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, pool_str: PoolStr<P, S>) -> Symbol {
+        if let Some(symbol) = self.symbols.get(&pool_str) {
+            return *symbol;
+        }
+
+        let id = u32::try_from(self.strings.len()).expect("SymbolMap: too many symbols");
+        let symbol = Symbol(id);
+        self.strings.push(pool_str.clone());
+        self.symbols.insert(pool_str, symbol);
+        symbol
+    }
+
+    /// Returns the string of a symbol
+    pub fn resolve(&self, symbol: Symbol) -> Option<&PoolStr<P, S>> {
+        self.strings.get(symbol.0 as usize)
+    }
+
+    /// Returns the symbol of a string
+    pub fn symbol(&self, string: &str) -> Option<Symbol> {
+        self.symbols.get(string).copied()
+    }
+
+    /// Number of symbols in this map
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Checks if this map contains no symbol
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Interns `strings` and assigns a [`Symbol`] to each distinct one
+    ///
+    /// The returned vector holds the symbol of each input string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `u32::MAX` distinct strings.
+    pub fn intern_build_symbols(&self, strings: &[&str]) -> (SymbolMap<P, S>, Vec<Symbol>) {
+        let mut map = SymbolMap::new();
+
+        let symbols = strings.iter().map(|string| map.insert(self.intern(string))).collect();
+        (map, symbols)
+    }
+}
+
+/// Normalizes, interns and assigns [`Symbol`]s to tokens
+///
+/// Symbols are kept across calls to [`InternPipeline::process`]:
+///
+/// ```rust
+/// # use strpool::Pool;
+/// let pool: Pool<4> = Pool::new();
+/// let mut pipeline = pool.intern_pipeline().lowercase(true);
+/// let symbols = pipeline.process(["SELECT", "x", "select"]);
+/// assert_eq!(symbols[0], symbols[2]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct InternPipeline<const P: usize = 1, const S: usize = 1024> {
+    pool: Pool<P, S>,
+    trim: bool,
+    lowercase: bool,
+    map: SymbolMap<P, S>,
+}
+
+impl<const P: usize, const S: usize> InternPipeline<P, S> {
+    /// Creates a pipeline interning into `pool`, without normalization
+    pub fn new(pool: Pool<P, S>) -> Self {
+        Self {
+            pool,
+            trim: false,
+            lowercase: false,
+            map: SymbolMap::new(),
+        }
+    }
+
+    /// Trims whitespace around tokens
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Converts tokens to ASCII lowercase
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
+
+    fn normalize<'a>(&self, token: &'a str) -> Cow<'a, str> {
+        let token = match self.trim {
+            true => token.trim(),
+            false => token,
+        };
+
+        match self.lowercase && token.bytes().any(|b| b.is_ascii_uppercase()) {
+            true => Cow::Owned(token.to_ascii_lowercase()),
+            false => Cow::Borrowed(token),
+        }
+    }
+
+    /// Returns the symbol of each token
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `u32::MAX` distinct tokens.
+    pub fn process<T: AsRef<str>, I: IntoIterator<Item = T>>(&mut self, tokens: I) -> Vec<Symbol> {
+        tokens.into_iter().map(|token| {
+            let pool_str = self.pool.intern(&self.normalize(token.as_ref()));
+            self.map.insert(pool_str)
+        }).collect()
+    }
+
+    /// Table of the symbols assigned so far
+    pub fn symbols(&self) -> &SymbolMap<P, S> {
+        &self.map
+    }
+
+    /// Pool in which tokens are interned
+    pub fn pool(&self) -> &Pool<P, S> {
+        &self.pool
+    }
+}
+
+impl<const P: usize, const S: usize> Pool<P, S> {
+    /// Creates an [`InternPipeline`] interning into this pool
+    pub fn intern_pipeline(&self) -> InternPipeline<P, S> {
+        InternPipeline::new(self.clone())
+    }
+}
+
+#[test]
+fn intern_build_symbols() {
+    let pool: Pool<4> = Pool::new();
+    let tokens = ["let", "x", "=", "x", "+", "1", ";", "let"];
+    let (map, symbols) = pool.intern_build_symbols(&tokens);
+
+    assert_eq!(map.len(), 6);
+    assert_eq!(symbols.len(), tokens.len());
+    assert_eq!(symbols[0], symbols[7]);
+    assert_eq!(symbols[1], symbols[3]);
+    assert_eq!(symbols[1].id(), 1);
+
+    for (token, symbol) in tokens.iter().zip(&symbols) {
+        assert_eq!(map.resolve(*symbol).unwrap(), token);
+        assert_eq!(map.symbol(token), Some(*symbol));
+    }
+
+    assert_eq!(map.symbol("fn"), None);
+    assert_eq!(map.resolve(Symbol(6)), None);
+    assert_eq!(map.resolve(symbols[4]), pool.find("+").as_ref());
+}
+
+#[test]
+fn intern_pipeline() {
+    let pool: Pool<4> = Pool::new();
+    let mut pipeline = pool.intern_pipeline().lowercase(true).trim(true);
+
+    let symbols = pipeline.process(["Let", " x", "LET ", "let", "X"]);
+    assert_eq!(symbols, [Symbol(0), Symbol(1), Symbol(0), Symbol(0), Symbol(1)]);
+
+    // symbols are kept across calls
+    let more = pipeline.process([alloc::string::String::from("y"), "x".into()]);
+    assert_eq!(more, [Symbol(2), Symbol(1)]);
+
+    let map = pipeline.symbols();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map.resolve(Symbol(0)).unwrap(), "let");
+    assert_eq!(map.resolve(Symbol(2)), pool.find("y").as_ref());
+    assert_eq!(pool.find("Let"), None);
+
+    // without normalization
+    let mut pipeline = InternPipeline::new(pool.clone());
+    assert_eq!(pipeline.process(["Let", "let", " let"]), [Symbol(0), Symbol(1), Symbol(2)]);
+}
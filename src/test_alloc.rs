@@ -0,0 +1,53 @@
+// global allocator counting allocations per thread, for tests;
+// it can also be made to fail on the current thread
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+std::thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static FAILING: Cell<bool> = const { Cell::new(false) };
+}
+
+struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+
+        match FAILING.try_with(|failing| failing.get()) {
+            Ok(true) => core::ptr::null_mut(),
+            _ => System.alloc(layout),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Allocator whose allocations always fail, for pools
+pub(crate) struct FailingAlloc;
+
+unsafe impl GlobalAlloc for FailingAlloc {
+    unsafe fn alloc(&self, _layout: Layout) -> *mut u8 {
+        core::ptr::null_mut()
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {
+        unreachable!("FailingAlloc never allocates")
+    }
+}
+
+/// Number of allocations made by the current thread so far
+pub(crate) fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+/// Makes the next allocations of the current thread fail, or not
+pub(crate) fn set_failing(failing: bool) {
+    FAILING.with(|cell| cell.set(failing));
+}
@@ -0,0 +1,83 @@
+use super::{Pool, PoolStr};
+
+/// Two pools where new strings overflow into the second one
+///
+/// Strings are interned in the primary pool until its memory
+/// usage exceeds a byte budget; new strings then go to the
+/// secondary pool. This is useful to keep hot strings apart
+/// from the cold ones interned later.
+#[derive(Debug, Clone)]
+pub struct TieredPool<const P: usize = 1, const S: usize = 1024> {
+    primary: Pool<P, S>,
+    secondary: Pool<P, S>,
+    budget: usize,
+}
+
+impl<const P: usize, const S: usize> TieredPool<P, S> {
+    /// Creates a new tiered pool with two new pools
+    pub fn new(budget: usize) -> Self {
+        Self::from_pools(Pool::new(), Pool::new(), budget)
+    }
+
+    /// Creates a new tiered pool from existing pools
+    pub fn from_pools(primary: Pool<P, S>, secondary: Pool<P, S>, budget: usize) -> Self {
+        Self {
+            primary,
+            secondary,
+            budget,
+        }
+    }
+
+    /// The pool used until the budget is exceeded
+    pub fn primary(&self) -> &Pool<P, S> {
+        &self.primary
+    }
+
+    /// The pool used once the budget is exceeded
+    pub fn secondary(&self) -> &Pool<P, S> {
+        &self.secondary
+    }
+
+    /// Locates an existing [`PoolStr`] in either pool
+    pub fn find(&self, string: &str) -> Option<PoolStr<P, S>> {
+        self.primary.find(string).or_else(|| self.secondary.find(string))
+    }
+
+    /// Creates a new [`PoolStr`]
+    pub fn intern(&self, string: &str) -> PoolStr<P, S> {
+        if let Some(pool_str) = self.find(string) {
+            return pool_str;
+        }
+
+        match self.primary.memory_usage() > self.budget {
+            true => self.secondary.intern(string),
+            false => self.primary.intern(string),
+        }
+    }
+}
+
+#[test]
+fn tiered_pool() {
+    let pool: TieredPool<1> = TieredPool::new(0);
+    assert!(pool.primary().memory_usage() > 0);
+
+    // the budget is already exceeded
+    pool.intern("cold");
+    assert_eq!(pool.primary().find("cold"), None);
+    assert_eq!(&*pool.secondary().find("cold").unwrap(), "cold");
+
+    let budget = pool.primary().memory_usage();
+    let pool: TieredPool<1> = TieredPool::new(budget);
+
+    // the first page makes the primary pool exceed its budget
+    pool.intern("hot");
+    pool.intern("cold");
+    assert_eq!(&*pool.primary().find("hot").unwrap(), "hot");
+    assert_eq!(&*pool.secondary().find("cold").unwrap(), "cold");
+
+    // both are searchable, and aren't stored twice
+    assert_eq!(&*pool.find("hot").unwrap(), "hot");
+    assert_eq!(&*pool.find("cold").unwrap(), "cold");
+    pool.intern("hot");
+    assert_eq!(pool.secondary().find("hot"), None);
+}
@@ -1,13 +1,14 @@
 use super::PoolStr;
-use core::ops::Deref;
+use core::{ops::Deref, cmp::Ordering};
+use alloc::{string::String, boxed::Box, borrow::Cow};
 
-impl<const P: usize> core::fmt::Debug for PoolStr<P> {
+impl<const P: usize, const S: usize> core::fmt::Debug for PoolStr<P, S> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         self.deref().fmt(f)
     }
 }
 
-impl<const P: usize> core::fmt::Display for PoolStr<P> {
+impl<const P: usize, const S: usize> core::fmt::Display for PoolStr<P, S> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         self.deref().fmt(f)
     }
@@ -19,46 +20,124 @@ impl<const P: usize> core::fmt::Display for PoolStr<P> {
 // for this reason, we have to fall back to
 // a traditional comparison if the pointers
 // aren't the same.
-impl<const P: usize> PartialEq for PoolStr<P> {
+impl<const P: usize, const S: usize> PartialEq for PoolStr<P, S> {
     fn eq(&self, other: &Self) -> bool {
-           self.len_ptr == other.len_ptr
-        || self.deref() == other.deref()
+        self.bytes_eq(other)
     }
 }
 
-impl<const P: usize> Eq for PoolStr <P>{}
+impl<const P: usize, const S: usize> Eq for PoolStr<P, S> {}
 
-impl<const P: usize> PartialEq<str> for PoolStr<P> {
+impl<const P: usize, const S: usize> PartialEq<str> for PoolStr<P, S> {
     fn eq(&self, other: &str) -> bool {
         self.deref() == other
     }
 }
 
-impl<const P: usize> PartialEq<PoolStr<P>> for str {
-    fn eq(&self, other: &PoolStr<P>) -> bool {
+impl<const P: usize, const S: usize> PartialEq<PoolStr<P, S>> for str {
+    fn eq(&self, other: &PoolStr<P, S>) -> bool {
         self == other.deref()
     }
 }
 
-impl<const P: usize> AsRef<str> for PoolStr<P> {
+impl<const P: usize, const S: usize> PartialEq<&str> for PoolStr<P, S> {
+    fn eq(&self, other: &&str) -> bool {
+        self.deref() == *other
+    }
+}
+
+impl<const P: usize, const S: usize> PartialEq<PoolStr<P, S>> for &str {
+    fn eq(&self, other: &PoolStr<P, S>) -> bool {
+        *self == other.deref()
+    }
+}
+
+impl<const P: usize, const S: usize> PartialEq<String> for PoolStr<P, S> {
+    fn eq(&self, other: &String) -> bool {
+        self.deref() == other.as_str()
+    }
+}
+
+impl<const P: usize, const S: usize> PartialEq<PoolStr<P, S>> for String {
+    fn eq(&self, other: &PoolStr<P, S>) -> bool {
+        self.as_str() == other.deref()
+    }
+}
+
+impl<'a, const P: usize, const S: usize> PartialEq<Cow<'a, str>> for PoolStr<P, S> {
+    fn eq(&self, other: &Cow<'a, str>) -> bool {
+        self.deref() == other.as_ref()
+    }
+}
+
+impl<'a, const P: usize, const S: usize> PartialEq<PoolStr<P, S>> for Cow<'a, str> {
+    fn eq(&self, other: &PoolStr<P, S>) -> bool {
+        self.as_ref() == other.deref()
+    }
+}
+
+impl<const P: usize, const S: usize> AsRef<str> for PoolStr<P, S> {
     fn as_ref(&self) -> &str {
         self.deref()
     }
 }
 
-impl<const P: usize> Default for PoolStr<P> {
+impl<const P: usize, const S: usize> From<&PoolStr<P, S>> for String {
+    fn from(pool_str: &PoolStr<P, S>) -> Self {
+        String::from(pool_str.as_str())
+    }
+}
+
+impl<const P: usize, const S: usize> From<PoolStr<P, S>> for String {
+    fn from(pool_str: PoolStr<P, S>) -> Self {
+        String::from(&pool_str)
+    }
+}
+
+impl<const P: usize, const S: usize> From<&PoolStr<P, S>> for Box<str> {
+    fn from(pool_str: &PoolStr<P, S>) -> Self {
+        Box::from(pool_str.as_str())
+    }
+}
+
+impl<const P: usize, const S: usize> From<PoolStr<P, S>> for Box<str> {
+    fn from(pool_str: PoolStr<P, S>) -> Self {
+        Box::from(&pool_str)
+    }
+}
+
+impl<const P: usize, const S: usize> PartialEq<[u8]> for PoolStr<P, S> {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.as_bytes() == other
+    }
+}
+
+impl<const P: usize, const S: usize> PartialEq<&[u8]> for PoolStr<P, S> {
+    fn eq(&self, other: &&[u8]) -> bool {
+        self.as_bytes() == *other
+    }
+}
+
+// Hash and Eq only depend on the string contents
+impl<const P: usize, const S: usize> core::borrow::Borrow<str> for PoolStr<P, S> {
+    fn borrow(&self) -> &str {
+        self.deref()
+    }
+}
+
+impl<const P: usize, const S: usize> Default for PoolStr<P, S> {
     fn default() -> Self {
         Self::empty()
     }
 }
 
-impl<const P: usize> core::hash::Hash for PoolStr<P> {
+impl<const P: usize, const S: usize> core::hash::Hash for PoolStr<P, S> {
     fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         self.deref().hash(state);
     }
 }
 
-impl<const P: usize, I> core::ops::Index<I> for PoolStr<P>
+impl<const P: usize, const S: usize, I> core::ops::Index<I> for PoolStr<P, S>
 where I: core::slice::SliceIndex<str>,
 {
     type Output = I::Output;
@@ -69,22 +148,123 @@ where I: core::slice::SliceIndex<str>,
     }
 }
 
-impl<const P: usize> PartialOrd<str> for PoolStr<P> {
+impl<const P: usize, const S: usize> PartialOrd<str> for PoolStr<P, S> {
     #[inline]
-    fn partial_cmp(&self, other: &str) -> Option<core::cmp::Ordering> {
+    fn partial_cmp(&self, other: &str) -> Option<Ordering> {
         self.deref().partial_cmp(other)
     }
 }
 
-impl<const P: usize> PartialOrd<PoolStr<P>> for PoolStr<P> {
+impl<const P: usize, const S: usize> PartialOrd<PoolStr<P, S>> for str {
     #[inline]
-    fn partial_cmp(&self, other: &PoolStr<P>) -> Option<core::cmp::Ordering> {
-        self.deref().partial_cmp(other.deref())
+    fn partial_cmp(&self, other: &PoolStr<P, S>) -> Option<Ordering> {
+        self.partial_cmp(other.deref())
     }
 }
 
-impl<const P: usize> Ord for PoolStr<P> {
-    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
-        self.deref().cmp(other.deref())
+impl<const P: usize, const S: usize> PartialOrd<&str> for PoolStr<P, S> {
+    #[inline]
+    fn partial_cmp(&self, other: &&str) -> Option<Ordering> {
+        self.deref().partial_cmp(*other)
+    }
+}
+
+impl<const P: usize, const S: usize> PartialOrd<PoolStr<P, S>> for &str {
+    #[inline]
+    fn partial_cmp(&self, other: &PoolStr<P, S>) -> Option<Ordering> {
+        (*self).partial_cmp(other.deref())
+    }
+}
+
+impl<const P: usize, const S: usize> PartialOrd<String> for PoolStr<P, S> {
+    #[inline]
+    fn partial_cmp(&self, other: &String) -> Option<Ordering> {
+        self.deref().partial_cmp(other.as_str())
+    }
+}
+
+impl<const P: usize, const S: usize> PartialOrd<PoolStr<P, S>> for String {
+    #[inline]
+    fn partial_cmp(&self, other: &PoolStr<P, S>) -> Option<Ordering> {
+        self.as_str().partial_cmp(other.deref())
+    }
+}
+
+impl<'a, const P: usize, const S: usize> PartialOrd<Cow<'a, str>> for PoolStr<P, S> {
+    #[inline]
+    fn partial_cmp(&self, other: &Cow<'a, str>) -> Option<Ordering> {
+        self.deref().partial_cmp(other.as_ref())
     }
 }
+
+impl<'a, const P: usize, const S: usize> PartialOrd<PoolStr<P, S>> for Cow<'a, str> {
+    #[inline]
+    fn partial_cmp(&self, other: &PoolStr<P, S>) -> Option<Ordering> {
+        self.as_ref().partial_cmp(other.deref())
+    }
+}
+
+impl<const P: usize, const S: usize> PartialOrd<PoolStr<P, S>> for PoolStr<P, S> {
+    #[inline]
+    fn partial_cmp(&self, other: &PoolStr<P, S>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Same pointer means same string, like in PartialEq
+impl<const P: usize, const S: usize> Ord for PoolStr<P, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.len_ptr == other.len_ptr {
+            true => Ordering::Equal,
+            false => self.deref().cmp(other.deref()),
+        }
+    }
+}
+
+#[test]
+fn into_owned() {
+    use super::Pool;
+
+    let pool: Pool<4> = Pool::new();
+    let pool_str = pool.intern("yikes");
+
+    assert_eq!(String::from(&pool_str), "yikes");
+    assert_eq!(&*Box::<str>::from(&pool_str), "yikes");
+    assert_eq!(String::from(PoolStr::<4>::empty()), "");
+
+    let boxed: Box<str> = pool_str.into();
+    assert_eq!(&*boxed, "yikes");
+}
+
+#[test]
+fn comparisons() {
+    use super::Pool;
+
+    let pool: Pool<4> = Pool::new();
+    let (a, b) = (pool.intern("a"), pool.intern("b"));
+    let (owned, cow): (String, Cow<str>) = ("b".into(), Cow::Borrowed("b"));
+    let c = String::from("c");
+
+    assert_eq!(b, "b");
+    assert_eq!("b", b);
+    assert_eq!(b, *"b");
+    assert_eq!(*"b", b);
+    assert_eq!(b, owned);
+    assert_eq!(owned, b);
+    assert_eq!(b, cow);
+    assert_eq!(cow, b);
+    assert_ne!(b, Cow::<str>::Owned("c".into()));
+
+    assert!(b > "a");
+    assert!("a" < b);
+    assert!(b > *"a");
+    assert!(*"a" < b);
+    assert!(b < c);
+    assert!(c > b);
+    assert!(b < Cow::Borrowed("c"));
+    assert!(Cow::Borrowed("c") > b);
+    assert_eq!(b.partial_cmp(&cow), Some(Ordering::Equal));
+
+    assert!(a < b);
+    assert_eq!(b.cmp(&b.clone()), Ordering::Equal);
+}